@@ -0,0 +1,88 @@
+// benches/validation.rs
+//
+// Measures how block validation scales with the number of rayon threads. The
+// context-free checks run in parallel; the full block application also includes the
+// sequential spend checks and state updates.
+
+use bc::amount::Amount;
+use bc::block::{BCBlock, BlockHash};
+use bc::context::{BCContext, Spentness};
+use bc::transaction::{Address, BCTransaction, Note, TXO};
+use bc::validation;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+const BLOCK_TRANSACTIONS: usize = 2_000;
+const THREADS: [usize; 4] = [1, 2, 4, 8];
+
+// Builds a funded context and a block in which every transaction spends one
+// transparent output and one shielded note into fresh outputs.
+fn setup(n: usize) -> (BCContext, BCBlock) {
+    let mut ctx = BCContext::new();
+    ctx.params.max_block_size = usize::MAX;
+
+    let txo = |index: usize, value: i64| TXO {
+        tx: BCTransaction::default(),
+        index,
+        value: Amount::const_from_i64(value),
+        address: Address(index as u32),
+    };
+    let note = |value: i64| Note { value: Amount::const_from_i64(value) };
+
+    let funding = BCTransaction {
+        transparent_outputs: (0..n).map(|i| txo(i, 10)).collect(),
+        shielded_outputs: (0..n).map(|i| note(i as i64 + 1)).collect(),
+        ..BCTransaction::default()
+    };
+    assert!(ctx.add_transaction(funding));
+
+    let transactions = (0..n)
+        .map(|i| {
+            let input = note(i as i64 + 1);
+            let mut anchor = BCContext::new();
+            anchor.notes.insert(input.clone(), Spentness::Unspent);
+            BCTransaction {
+                transparent_inputs: vec![txo(i, 10)],
+                transparent_outputs: vec![txo(n + i, 9)],
+                shielded_inputs: vec![input],
+                shielded_outputs: vec![note((n + i) as i64 + 1)],
+                fee: Amount::const_from_i64(1),
+                anchor: Some(anchor),
+                ..BCTransaction::default()
+            }
+        })
+        .collect();
+    let block = BCBlock {
+        parent: None,
+        score: 1,
+        transactions,
+        hash: BlockHash::from_rng(&mut StdRng::seed_from_u64(0)),
+        state_root: None,
+    };
+    (ctx, block)
+}
+
+fn bench_validation(c: &mut Criterion) {
+    let (ctx, block) = setup(BLOCK_TRANSACTIONS);
+
+    let mut group = c.benchmark_group("validation");
+    group.throughput(Throughput::Elements(BLOCK_TRANSACTIONS as u64));
+    group.sample_size(10);
+    for threads in THREADS {
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+        group.bench_with_input(BenchmarkId::new("context_free", threads), &threads, |b, _| {
+            b.iter(|| pool.install(|| validation::check_block_context_free(&ctx, &block).unwrap()))
+        });
+        group.bench_with_input(BenchmarkId::new("add_block", threads), &threads, |b, _| {
+            b.iter(|| {
+                let mut next = ctx.copy();
+                pool.install(|| validation::try_add_block(&mut next, &block).unwrap())
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_validation);
+criterion_main!(benches);
@@ -0,0 +1,154 @@
+//! # Amounts
+//!
+//! This module defines `Amount`, a checked monetary value in zatoshi.
+//!
+//! Amounts are 64-bit and always within `-MAX_MONEY..=MAX_MONEY`, which is enforced on
+//! construction, on deserialization and by every arithmetic operation. Sums that leave
+//! the range produce `None` (or an [`AmountError`]) instead of wrapping.
+
+use serde::{Serialize, Deserialize};
+use crate::commitment::Encode;
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, Neg, Sub};
+
+/// Number of zatoshi in one ZEC.
+pub const COIN: i64 = 100_000_000;
+
+/// Maximum total supply, in zatoshi.
+pub const MAX_MONEY: i64 = 21_000_000 * COIN;
+
+/// Error returned when a value is outside the valid range for an [`Amount`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AmountError;
+
+impl fmt::Display for AmountError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "amount outside the range -MAX_MONEY..=MAX_MONEY")
+    }
+}
+
+impl std::error::Error for AmountError {}
+
+/// A monetary value in zatoshi, in the range `-MAX_MONEY..=MAX_MONEY`.
+///
+/// Negative amounts are used for fees claimed by coinbase transactions.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[derive(Eq, Hash, PartialEq, Ord, PartialOrd)]
+#[serde(try_from = "i64", into = "i64")]
+pub struct Amount(i64);
+
+impl Amount {
+    /// The zero amount.
+    pub const ZERO: Amount = Amount(0);
+
+    /// Creates an amount, checking that it is within range.
+    pub fn from_i64(zatoshi: i64) -> Result<Self, AmountError> {
+        if (-MAX_MONEY..=MAX_MONEY).contains(&zatoshi) {
+            Ok(Amount(zatoshi))
+        } else {
+            Err(AmountError)
+        }
+    }
+
+    /// Creates a non-negative amount, checking that it is within range.
+    pub fn from_nonnegative_i64(zatoshi: i64) -> Result<Self, AmountError> {
+        if zatoshi < 0 {
+            return Err(AmountError);
+        }
+        Self::from_i64(zatoshi)
+    }
+
+    /// Creates an amount from a constant.
+    ///
+    /// Panics if the value is out of range.
+    pub const fn const_from_i64(zatoshi: i64) -> Self {
+        assert!(-MAX_MONEY <= zatoshi && zatoshi <= MAX_MONEY, "amount out of range");
+        Amount(zatoshi)
+    }
+
+    /// Returns the value in zatoshi.
+    pub const fn zatoshi(self) -> i64 {
+        self.0
+    }
+
+    /// Checks if the amount is negative.
+    pub const fn is_negative(self) -> bool {
+        self.0 < 0
+    }
+
+    /// Checks if the amount is positive.
+    pub const fn is_positive(self) -> bool {
+        self.0 > 0
+    }
+}
+
+impl Encode for Amount {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.0.encode(out);
+    }
+}
+
+impl TryFrom<i64> for Amount {
+    type Error = AmountError;
+
+    fn try_from(zatoshi: i64) -> Result<Self, AmountError> {
+        Amount::from_i64(zatoshi)
+    }
+}
+
+impl From<Amount> for i64 {
+    fn from(amount: Amount) -> i64 {
+        amount.0
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} zat", self.0)
+    }
+}
+
+impl Add for Amount {
+    type Output = Option<Amount>;
+
+    fn add(self, rhs: Amount) -> Option<Amount> {
+        Amount::from_i64(self.0 + rhs.0).ok()
+    }
+}
+
+impl Add<Amount> for Option<Amount> {
+    type Output = Option<Amount>;
+
+    fn add(self, rhs: Amount) -> Option<Amount> {
+        self.and_then(|lhs| lhs + rhs)
+    }
+}
+
+impl Sub for Amount {
+    type Output = Option<Amount>;
+
+    fn sub(self, rhs: Amount) -> Option<Amount> {
+        Amount::from_i64(self.0 - rhs.0).ok()
+    }
+}
+
+impl Neg for Amount {
+    type Output = Amount;
+
+    fn neg(self) -> Amount {
+        Amount(-self.0)
+    }
+}
+
+impl Sum<Amount> for Option<Amount> {
+    fn sum<I: Iterator<Item = Amount>>(mut iter: I) -> Self {
+        iter.try_fold(Amount::ZERO, |acc, amount| acc + amount)
+    }
+}
+
+impl<'a> Sum<&'a Amount> for Option<Amount> {
+    fn sum<I: Iterator<Item = &'a Amount>>(iter: I) -> Self {
+        iter.copied().sum()
+    }
+}
@@ -0,0 +1,171 @@
+//! # Anonymity Sets
+//!
+//! This module measures the anonymity sets of shielded spends.
+//!
+//! A shielded spend proves that its note is in the note tree at its anchor without
+//! revealing which note it is. Since the simulator knows every note and when it is
+//! spent, the anonymity set of a spend is the set of notes that were unspent in the
+//! anchor's context and are still unspent when the spend is mined. Older anchors, such
+//! as those a wallet must use while waiting for finality, contain fewer notes, and more
+//! of their notes have been spent since.
+
+use std::fmt::Write as _;
+use crate::block::BCBlock;
+use crate::context::{BCContext, Spentness};
+use crate::traits::ContextTrait;
+use crate::transaction::{BCTransaction, Note, TxId};
+use crate::validation;
+
+/// The anonymity set of a single shielded spend.
+///
+/// ## Fields
+/// - `txid`: The id of the spending transaction.
+/// - `note`: The note being spent.
+/// - `height`: The height at which the spend is mined.
+/// - `anchor_height`: The height of the anchor's context.
+/// - `anchor_notes`: The number of unspent notes in the anchor's context.
+/// - `anonymity_set`: The number of those notes still unspent when the spend is mined.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpendAnonymity {
+    pub txid: TxId,
+    pub note: Note,
+    pub height: u32,
+    pub anchor_height: u32,
+    pub anchor_notes: usize,
+    pub anonymity_set: usize,
+}
+
+impl SpendAnonymity {
+    /// Returns the number of blocks between the anchor and the spend.
+    pub fn anchor_age(&self) -> u32 {
+        self.height.saturating_sub(self.anchor_height)
+    }
+}
+
+/// Returns the number of notes unspent in `anchor` that are still unspent in `context`.
+pub fn anonymity_set_size(anchor: &BCContext, context: &BCContext) -> usize {
+    anchor
+        .notes
+        .iter()
+        .filter(|(note, spent)| **spent == Spentness::Unspent && !context.is_spent(note))
+        .count()
+}
+
+/// Returns the anonymity sets of a transaction's shielded spends against the context
+/// it is about to be applied to.
+///
+/// Spends without an anchor have no anonymity set and are skipped.
+pub fn spend_anonymity(context: &BCContext, tx: &BCTransaction) -> Vec<SpendAnonymity> {
+    let Some(anchor) = &tx.anchor else {
+        return Vec::new();
+    };
+    let anchor_notes = anchor.notes.values().filter(|spent| **spent == Spentness::Unspent).count();
+    let anonymity_set = anonymity_set_size(anchor, context);
+    let txid = tx.txid();
+    tx.shielded_inputs
+        .iter()
+        .map(|note| SpendAnonymity {
+            txid,
+            note: note.clone(),
+            height: context.height,
+            anchor_height: anchor.height,
+            anchor_notes,
+            anonymity_set,
+        })
+        .collect()
+}
+
+/// The anonymity sets of all shielded spends in a chain.
+///
+/// ## Fields
+/// - `spends`: The anonymity set of every shielded spend, in chain order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AnonymityReport {
+    pub spends: Vec<SpendAnonymity>,
+}
+
+impl AnonymityReport {
+    /// Replays a chain of blocks and records the anonymity set of every shielded spend.
+    ///
+    /// ## Parameters
+    /// - `initial_context`: The context the first block is applied to.
+    /// - `blocks`: The blocks of the chain, from the first to the tip.
+    ///
+    /// ## Returns
+    /// The report for the blocks up to the first invalid one.
+    pub fn from_chain(initial_context: &BCContext, blocks: &[BCBlock]) -> Self {
+        let mut context = initial_context.copy();
+        let mut spends = Vec::new();
+        for block in blocks {
+            // Only measure blocks that are valid, since applying an invalid one may fail.
+            let mut validated = context.copy();
+            if validation::try_add_block(&mut validated, block).is_err() {
+                break;
+            }
+            // Measure each spend against the state left by the preceding transactions.
+            for tx in &block.transactions {
+                spends.extend(spend_anonymity(&context, tx));
+                context.apply_transaction(tx.clone());
+            }
+            context = validated;
+        }
+        Self { spends }
+    }
+
+    /// Returns the mean anonymity set size, or `None` if there are no spends.
+    pub fn mean_anonymity_set(&self) -> Option<f64> {
+        if self.spends.is_empty() {
+            return None;
+        }
+        Some(self.spends.iter().map(|spend| spend.anonymity_set as f64).sum::<f64>() / self.spends.len() as f64)
+    }
+
+    /// Returns the smallest anonymity set, or `None` if there are no spends.
+    pub fn min_anonymity_set(&self) -> Option<usize> {
+        self.spends.iter().map(|spend| spend.anonymity_set).min()
+    }
+
+    /// Returns the spends as CSV with a
+    /// `height,anchor_height,anchor_age,anchor_notes,anonymity_set` header.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("height,anchor_height,anchor_age,anchor_notes,anonymity_set\n");
+        for spend in &self.spends {
+            writeln!(
+                csv,
+                "{},{},{},{},{}",
+                spend.height,
+                spend.anchor_height,
+                spend.anchor_age(),
+                spend.anchor_notes,
+                spend.anonymity_set,
+            )
+            .unwrap();
+        }
+        csv
+    }
+}
+
+/// Returns the mean anonymity set a spend would get with an anchor at each depth.
+///
+/// This shows the cost of waiting for finality before using an anchor: a spend at
+/// height `h` with an anchor at depth `d` uses the context after `h - d` blocks.
+///
+/// ## Parameters
+/// - `history`: The context after each block, so that `history[h]` is the context at height `h`.
+/// - `depths`: The anchor depths to evaluate.
+///
+/// ## Returns
+/// For each depth, the mean anonymity set size over all heights at least that deep,
+/// or `None` if there is no such height.
+pub fn anonymity_by_anchor_depth(history: &[BCContext], depths: &[u32]) -> Vec<(u32, Option<f64>)> {
+    depths
+        .iter()
+        .map(|&depth| {
+            let sizes: Vec<usize> = (depth as usize..history.len())
+                .map(|height| anonymity_set_size(&history[height - depth as usize], &history[height]))
+                .collect();
+            let mean = (!sizes.is_empty()).then(|| sizes.iter().sum::<usize>() as f64 / sizes.len() as f64);
+            (depth, mean)
+        })
+        .collect()
+}
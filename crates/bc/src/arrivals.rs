@@ -0,0 +1,272 @@
+//! # Block Arrivals
+//!
+//! This module models when blocks are produced in a best-chain run.
+//!
+//! An [`ArrivalProcess`] yields the intervals between consecutive blocks. The baseline
+//! [`PoissonArrivals`] draws exponential intervals; [`TraceArrivals`] replays intervals
+//! read from a local CSV file, such as block header times exported from mainnet, so that
+//! runs reflect real-world variance. [`simulate_mining`] turns arrival times into a block
+//! tree with forks caused by propagation delay, ready for [`ChainMetrics`](crate::metrics::ChainMetrics).
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use crate::block::{BCBlock, BlockHash};
+use crate::metrics::ObservedBlock;
+
+/// A source of intervals between consecutive blocks.
+pub trait ArrivalProcess {
+    /// Returns the time until the next block, or `None` if the process is exhausted.
+    fn next_interval(&mut self) -> Option<f64>;
+
+    /// Returns the arrival times of the next `n` blocks, relative to the current time.
+    ///
+    /// Fewer times are returned if the process is exhausted.
+    fn arrival_times(&mut self, n: usize) -> Vec<f64> {
+        let mut time = 0.0;
+        let mut times = Vec::with_capacity(n);
+        while times.len() < n {
+            match self.next_interval() {
+                Some(interval) => {
+                    time += interval;
+                    times.push(time);
+                }
+                None => break,
+            }
+        }
+        times
+    }
+}
+
+/// Block arrivals as a Poisson process, with exponentially distributed intervals.
+///
+/// ## Fields
+/// - `mean_interval`: The mean time between blocks.
+/// - `rng`: The random number generator.
+#[derive(Debug, Clone)]
+pub struct PoissonArrivals {
+    pub mean_interval: f64,
+    pub rng: StdRng,
+}
+
+impl PoissonArrivals {
+    /// Creates a Poisson process with the given mean interval and seed.
+    pub fn new(mean_interval: f64, seed: u64) -> Self {
+        Self {
+            mean_interval,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl ArrivalProcess for PoissonArrivals {
+    fn next_interval(&mut self) -> Option<f64> {
+        let u: f64 = self.rng.gen();
+        Some(-self.mean_interval * (1.0 - u).ln())
+    }
+}
+
+/// Error reading a trace of block intervals.
+#[derive(Debug)]
+pub enum TraceError {
+    /// The trace file could not be read.
+    Io(io::Error),
+    /// A value on the given line (1-based) is not a number.
+    Parse { line: usize },
+    /// An interval on the given line (1-based) is negative or not finite, or a timestamp decreases.
+    Negative { line: usize },
+    /// The header has no `interval`, `time` or `timestamp` column and more than one column.
+    MissingColumn,
+    /// The trace contains no intervals.
+    Empty,
+}
+
+impl fmt::Display for TraceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TraceError::Io(err) => write!(f, "Cannot read trace: {}", err),
+            TraceError::Parse { line } => write!(f, "Invalid number on line {}", line),
+            TraceError::Negative { line } => write!(f, "Negative interval on line {}", line),
+            TraceError::MissingColumn => write!(f, "No interval, time or timestamp column"),
+            TraceError::Empty => write!(f, "Trace contains no intervals"),
+        }
+    }
+}
+
+impl std::error::Error for TraceError {}
+
+impl From<io::Error> for TraceError {
+    fn from(err: io::Error) -> Self {
+        TraceError::Io(err)
+    }
+}
+
+/// Block arrivals replayed from a trace of historical intervals.
+///
+/// ## Fields
+/// - `intervals`: The intervals between consecutive blocks.
+/// - `position`: The index of the next interval.
+/// - `cycle`: Whether to restart from the first interval once the trace is exhausted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceArrivals {
+    pub intervals: Vec<f64>,
+    pub position: usize,
+    pub cycle: bool,
+}
+
+impl TraceArrivals {
+    /// Creates a trace from a list of intervals.
+    pub fn new(intervals: Vec<f64>) -> Result<Self, TraceError> {
+        if intervals.is_empty() {
+            return Err(TraceError::Empty);
+        }
+        if let Some(index) = intervals.iter().position(|interval| !interval.is_finite() || *interval < 0.0) {
+            return Err(TraceError::Negative { line: index + 1 });
+        }
+        Ok(Self { intervals, position: 0, cycle: false })
+    }
+
+    /// Creates a trace from the intervals between consecutive block times.
+    pub fn from_timestamps(times: &[f64]) -> Result<Self, TraceError> {
+        Self::new(times.windows(2).map(|pair| pair[1] - pair[0]).collect())
+    }
+
+    /// Parses a trace from CSV text.
+    ///
+    /// The first column is used unless the first line is a header naming an `interval`
+    /// column, or a `time` or `timestamp` column, in which case intervals are the
+    /// differences between consecutive times. Blank lines are skipped.
+    pub fn from_csv_str(csv: &str) -> Result<Self, TraceError> {
+        let mut lines = csv.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()).peekable();
+
+        // Detect a header by its first field not being a number
+        let mut column = 0;
+        let mut timestamps = false;
+        if let Some((_, header)) = lines.peek() {
+            let fields: Vec<String> = header.split(',').map(|field| field.trim().to_lowercase()).collect();
+            if fields[0].parse::<f64>().is_err() {
+                if let Some(index) = fields.iter().position(|field| field == "interval") {
+                    column = index;
+                } else if let Some(index) = fields.iter().position(|field| field == "time" || field == "timestamp") {
+                    column = index;
+                    timestamps = true;
+                } else if fields.len() > 1 {
+                    return Err(TraceError::MissingColumn);
+                }
+                lines.next();
+            }
+        }
+
+        let mut values = Vec::new();
+        for (index, line) in lines {
+            let field = line.split(',').nth(column).ok_or(TraceError::Parse { line: index + 1 })?;
+            let value = field.trim().parse::<f64>().map_err(|_| TraceError::Parse { line: index + 1 })?;
+            values.push((index + 1, value));
+        }
+
+        if timestamps {
+            if let Some(pair) = values.windows(2).find(|pair| pair[1].1 < pair[0].1) {
+                return Err(TraceError::Negative { line: pair[1].0 });
+            }
+            let times: Vec<f64> = values.iter().map(|(_, time)| *time).collect();
+            return Self::from_timestamps(&times);
+        }
+        if let Some((line, _)) = values.iter().find(|(_, value)| !value.is_finite() || *value < 0.0) {
+            return Err(TraceError::Negative { line: *line });
+        }
+        Self::new(values.into_iter().map(|(_, value)| value).collect())
+    }
+
+    /// Reads a trace from a local CSV file.
+    ///
+    /// See [`TraceArrivals::from_csv_str`] for the accepted format.
+    pub fn from_csv_file(path: &Path) -> Result<Self, TraceError> {
+        Self::from_csv_str(&fs::read_to_string(path)?)
+    }
+
+    /// Restarts from the first interval once the trace is exhausted.
+    pub fn cycled(mut self) -> Self {
+        self.cycle = true;
+        self
+    }
+
+    /// Returns the mean interval of the trace.
+    pub fn mean_interval(&self) -> f64 {
+        self.intervals.iter().sum::<f64>() / self.intervals.len() as f64
+    }
+
+    /// Rescales the intervals to the given mean, keeping their relative variance.
+    ///
+    /// This allows a mainnet trace to drive runs with a different target block time.
+    pub fn with_mean_interval(mut self, mean_interval: f64) -> Self {
+        let factor = mean_interval / self.mean_interval();
+        if factor.is_finite() {
+            self.intervals.iter_mut().for_each(|interval| *interval *= factor);
+        }
+        self
+    }
+}
+
+impl ArrivalProcess for TraceArrivals {
+    fn next_interval(&mut self) -> Option<f64> {
+        if self.position == self.intervals.len() && self.cycle {
+            self.position = 0;
+        }
+        let interval = self.intervals.get(self.position).copied()?;
+        self.position += 1;
+        Some(interval)
+    }
+}
+
+/// Parameters of a mining run.
+///
+/// ## Fields
+/// - `blocks`: The number of blocks to produce after the genesis block.
+/// - `propagation_delay`: The time a block takes to reach every miner.
+/// - `honest_fraction`: The probability that a block is produced by an honest miner.
+/// - `seed`: The seed for choosing which blocks are honest.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MiningConfig {
+    pub blocks: usize,
+    pub propagation_delay: f64,
+    pub honest_fraction: f64,
+    pub seed: u64,
+}
+
+/// Produces empty blocks at the times given by an arrival process.
+///
+/// Every block extends the best block that had propagated when it was produced, that
+/// is, the highest block produced at least `propagation_delay` earlier (ties go to the
+/// earliest). Blocks produced closer together than the delay therefore fork. The
+/// genesis block is produced at time `0`.
+///
+/// ## Returns
+/// The blocks in arrival order, including the genesis block. Fewer than
+/// `config.blocks` blocks follow genesis if the arrival process is exhausted.
+pub fn simulate_mining<A: ArrivalProcess>(arrivals: &mut A, config: &MiningConfig) -> Vec<ObservedBlock> {
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let block = |parent: Option<BlockHash>, rng: &mut StdRng| BCBlock {
+        parent,
+        score: 1,
+        transactions: vec![],
+        hash: BlockHash::from_rng(rng),
+        state_root: None,
+    };
+
+    let mut blocks = vec![ObservedBlock { block: block(None, &mut rng), time: 0.0, honest: true }];
+    let mut heights = vec![0u32];
+    for time in arrivals.arrival_times(config.blocks) {
+        // Blocks are in arrival order, so the visible ones are a prefix.
+        let visible = blocks.partition_point(|observed| observed.time <= time - config.propagation_delay).max(1);
+        let parent = (0..visible).rev().max_by_key(|index| heights[*index]).unwrap();
+
+        let honest = rng.gen_bool(config.honest_fraction.clamp(0.0, 1.0));
+        let parent_hash = blocks[parent].block.hash;
+        blocks.push(ObservedBlock { block: block(Some(parent_hash), &mut rng), time, honest });
+        heights.push(heights[parent] + 1);
+    }
+    blocks
+}
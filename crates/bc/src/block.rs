@@ -7,51 +7,69 @@
 /// - `score`: The block's score relative to the parent.
 /// - `transactions`: The list of transactions included in the block.
 /// - `hash`: The unique hash of the block.
+/// - `state_root`: The commitment to the state after applying the block, if the block commits to one.
 use serde::{Serialize, Deserialize};
 use rand::Rng;
+use crate::commitment::StateRoot;
+use crate::traits::TransactionTrait;
+use crate::transaction::BCTransaction;
+
+/// Size in bytes of a serialized block header, including the Equihash solution.
+pub const BLOCK_HEADER_SIZE: usize = 1_487;
 
 /// A block in a best-chain protocol.
+/// 
+/// Blocks are generic over their transaction model, which defaults to `BCTransaction`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct BCBlock {
+pub struct BCBlock<T = BCTransaction> {
     /// Parent block hash
     pub parent: Option<BlockHash>,
     /// Block score (relative to the parent)
     pub score: i32,
     /// Transactions in this block
-    pub transactions: Vec<super::transaction::BCTransaction>,
+    pub transactions: Vec<T>,
     /// Block hash
     pub hash: BlockHash,
+    /// Commitment to the state after applying the block (not checked if `None`)
+    pub state_root: Option<StateRoot>,
+}
+
+impl<T: TransactionTrait> BCBlock<T> {
+    /// Returns the estimated serialized size of the block in bytes.
+    pub fn size(&self) -> usize {
+        BLOCK_HEADER_SIZE + self.transactions.iter().map(|tx| tx.size()).sum::<usize>()
+    }
 }
 
 /// Unique value representing a best-chain block hash.
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Eq, Hash, PartialEq)]
 pub struct BlockHash(u64);
 
-impl Default for BlockHash {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
 impl BlockHash {
-    /// Create a new random block hash.
-    pub fn new() -> Self {
-        let mut rng = rand::thread_rng();
+    /// Create a block hash drawn from the given random number generator.
+    ///
+    /// Use a seeded generator to make runs reproducible.
+    pub fn from_rng<R: Rng + ?Sized>(rng: &mut R) -> Self {
         BlockHash(rng.gen())
     }
 }
 
 /// Trait for block operations.
 pub trait BlockTrait {
+    /// The transaction model of the block.
+    type Transaction;
     /// Get the parent block hash.
     fn parent(&self) -> &Option<BlockHash>;
     /// Get the block score.
     fn score(&self) -> i32;
     /// Get the transactions in this block.
-    fn transactions(&self) -> &Vec<super::transaction::BCTransaction>;
+    fn transactions(&self) -> &[Self::Transaction];
 }
 
-impl BlockTrait for BCBlock {
+impl<T> BlockTrait for BCBlock<T> {
+    type Transaction = T;
+
     fn parent(&self) -> &Option<BlockHash> {
         &self.parent
     }
@@ -60,7 +78,7 @@ impl BlockTrait for BCBlock {
         self.score
     }
 
-    fn transactions(&self) -> &Vec<super::transaction::BCTransaction> {
+    fn transactions(&self) -> &[T] {
         &self.transactions
     }
 }
@@ -0,0 +1,236 @@
+//! # Block Tree
+//!
+//! This module implements a tree of best-chain blocks, generic over the context
+//! (and therefore the transaction model) used to validate them.
+//!
+//! Every block is validated by applying it to a copy of its parent's context, and the
+//! resulting context is kept with the block so that forks can be extended independently.
+//! The best chain is the one with the highest total score; ties are broken in favour of
+//! the block seen first. This fork-choice rule is shared through [`fork_choice`] with the
+//! light client and chain metrics.
+
+use std::collections::{HashMap, HashSet};
+use crate::block::{BCBlock, BlockHash};
+use crate::traits::ContextTrait;
+use crate::validation;
+
+/// A block tree entry that fork choice can rank.
+pub trait ChainWork {
+    /// Returns the height of the block (the genesis block has height 0).
+    fn height(&self) -> u32;
+
+    /// Returns the sum of the scores of the block and all its ancestors.
+    fn total_work(&self) -> i64;
+}
+
+/// Where a new block joins a tree, as decided by [`fork_choice`].
+///
+/// ## Fields
+/// - `height`: The height of the block, one more than its parent's.
+/// - `total_work`: The sum of the scores of the block and all its ancestors.
+/// - `is_best`: Whether the block becomes the tip of the best chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ForkChoice {
+    pub height: u32,
+    pub total_work: i64,
+    pub is_best: bool,
+}
+
+/// Places a new block in a tree and decides whether it becomes the best tip.
+///
+/// The best chain is the one with the highest total score; ties are broken in favour of
+/// the block seen first, so the tip only moves to a block with strictly more work.
+///
+/// ## Parameters
+/// - `entries`: The blocks already in the tree, by hash.
+/// - `tip`: The hash of the current best tip, if any.
+/// - `parent`: The parent of the new block, or `None` for a genesis block.
+/// - `score`: The score of the new block.
+///
+/// ## Returns
+/// `None` if the parent is not in the tree, or if the block is a second genesis block.
+pub fn fork_choice<E: ChainWork>(
+    entries: &HashMap<BlockHash, E>,
+    tip: Option<&BlockHash>,
+    parent: Option<&BlockHash>,
+    score: i32,
+) -> Option<ForkChoice> {
+    let (height, parent_work) = match parent {
+        Some(parent) => {
+            let entry = entries.get(parent)?;
+            (entry.height() + 1, entry.total_work())
+        }
+        None if entries.is_empty() => (0, 0),
+        None => return None,
+    };
+    let total_work = parent_work + score as i64;
+    let is_best = tip
+        .and_then(|tip| entries.get(tip))
+        .is_none_or(|tip| total_work > tip.total_work());
+    Some(ForkChoice { height, total_work, is_best })
+}
+
+/// A block in the tree together with its chain position and resulting context.
+///
+/// ## Fields
+/// - `block`: The block itself.
+/// - `height`: The height of the block (the genesis block has height 0).
+/// - `total_score`: The sum of the scores of the block and all its ancestors.
+/// - `context`: The context after applying the block.
+#[derive(Debug, Clone)]
+pub struct BlockEntry<C: ContextTrait> {
+    pub block: BCBlock<C::Transaction>,
+    pub height: u32,
+    pub total_score: i64,
+    pub context: C,
+}
+
+impl<C: ContextTrait> ChainWork for BlockEntry<C> {
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn total_work(&self) -> i64 {
+        self.total_score
+    }
+}
+
+/// A tree of blocks rooted at a single genesis block.
+///
+/// ## Fields
+/// - `initial_context`: The context the genesis block is applied to.
+/// - `blocks`: All blocks accepted into the tree, by hash.
+/// - `tip`: The hash of the tip of the best chain, if any block has been added.
+#[derive(Debug, Clone)]
+pub struct BlockTree<C: ContextTrait> {
+    pub initial_context: C,
+    pub blocks: HashMap<BlockHash, BlockEntry<C>>,
+    pub tip: Option<BlockHash>,
+}
+
+impl<C: ContextTrait> BlockTree<C> {
+    /// Creates an empty block tree.
+    ///
+    /// ## Parameters
+    /// - `initial_context`: The context the genesis block is applied to.
+    pub fn new(initial_context: C) -> Self {
+        Self {
+            initial_context,
+            blocks: HashMap::new(),
+            tip: None,
+        }
+    }
+
+    /// Adds a block to the tree.
+    ///
+    /// The block's parent must already be in the tree, or the block must be the first
+    /// block without a parent (the genesis block). The best tip moves to the new block
+    /// if its total score is strictly higher than the current tip's.
+    ///
+    /// ## Returns
+    /// - `true` if the block is valid and was added.
+    /// - `false` if it is a duplicate, an orphan, a second genesis block, or invalid.
+    pub fn add_block(&mut self, block: BCBlock<C::Transaction>) -> bool {
+        if self.blocks.contains_key(&block.hash) {
+            return false;
+        }
+
+        let Some(choice) = fork_choice(&self.blocks, self.tip.as_ref(), block.parent.as_ref(), block.score) else {
+            return false;
+        };
+        let mut context = match &block.parent {
+            Some(parent) => self.blocks[parent].context.copy(),
+            None => self.initial_context.copy(),
+        };
+
+        // The height comes from the context, which may not start at height 0.
+        let height = context.height();
+        if !validation::add_block(&mut context, &block) {
+            return false;
+        }
+
+        let hash = block.hash;
+        let total_score = choice.total_work;
+        self.blocks.insert(hash, BlockEntry { block, height, total_score, context });
+        if choice.is_best {
+            self.tip = Some(hash);
+        }
+        true
+    }
+
+    /// Returns the block with the given hash, if it is in the tree.
+    pub fn get(&self, hash: &BlockHash) -> Option<&BlockEntry<C>> {
+        self.blocks.get(hash)
+    }
+
+    /// Checks if the block with the given hash is in the tree.
+    pub fn contains(&self, hash: &BlockHash) -> bool {
+        self.blocks.contains_key(hash)
+    }
+
+    /// Returns the number of blocks in the tree.
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Checks if the tree has no blocks.
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    /// Returns the tip of the best chain, if any block has been added.
+    pub fn tip(&self) -> Option<&BlockEntry<C>> {
+        self.tip.as_ref().and_then(|hash| self.blocks.get(hash))
+    }
+
+    /// Returns the context at the tip of the best chain.
+    ///
+    /// This is the initial context if no block has been added.
+    pub fn tip_context(&self) -> &C {
+        self.tip().map_or(&self.initial_context, |entry| &entry.context)
+    }
+
+    /// Returns the hashes of a block and its ancestors, from the block back to genesis.
+    pub fn ancestors(&self, hash: &BlockHash) -> Vec<BlockHash> {
+        let mut chain = Vec::new();
+        let mut current = self.blocks.get(hash);
+        while let Some(entry) = current {
+            chain.push(entry.block.hash);
+            current = entry.block.parent.as_ref().and_then(|parent| self.blocks.get(parent));
+        }
+        chain
+    }
+
+    /// Returns the hashes of the best chain, from genesis to the tip.
+    pub fn best_chain(&self) -> Vec<BlockHash> {
+        let mut chain = self.tip.map_or_else(Vec::new, |tip| self.ancestors(&tip));
+        chain.reverse();
+        chain
+    }
+
+    /// Returns the blocks to disconnect and connect to move the best chain from one tip to another.
+    ///
+    /// ## Parameters
+    /// - `from`: The current tip, or `None` if no block is connected.
+    /// - `to`: The new tip.
+    ///
+    /// ## Returns
+    /// The blocks to disconnect, from `from` back to the fork point, and the blocks to
+    /// connect, from the fork point up to `to`. The fork point itself is in neither.
+    pub fn reorg_path(&self, from: Option<&BlockHash>, to: &BlockHash) -> (Vec<BlockHash>, Vec<BlockHash>) {
+        let old_chain = from.map_or_else(Vec::new, |from| self.ancestors(from));
+        let mut new_chain = self.ancestors(to);
+
+        let old_set: HashSet<BlockHash> = old_chain.iter().copied().collect();
+        let fork = new_chain.iter().position(|hash| old_set.contains(hash));
+        let fork_hash = fork.map(|position| new_chain[position]);
+        new_chain.truncate(fork.unwrap_or(new_chain.len()));
+        new_chain.reverse();
+
+        let disconnect = old_chain
+            .into_iter()
+            .take_while(|hash| Some(*hash) != fork_hash)
+            .collect();
+        (disconnect, new_chain)
+    }
+}
@@ -0,0 +1,368 @@
+//! # State Commitments
+//!
+//! This module implements an authenticated commitment to the state of a `BCContext`.
+//!
+//! The UTXO set, the set of created notes and the set of spent notes (nullifiers) are each
+//! kept in a sparse Merkle tree keyed by the BLAKE2b digest of their elements. The state
+//! root hashes the three tree roots together with the pool balances, so two contexts agree
+//! on their state exactly when their state roots are equal. This relies on validation never
+//! letting a note be created twice, so that each note is created and spent at most once. Trees are updated
+//! incrementally, so each insertion or removal costs `TREE_DEPTH` hashes. Elements are
+//! digested through their [`Encode`] byte encoding, which is the same on every platform.
+
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Depth of the sparse Merkle trees; elements are keyed by 64 bits of their digest.
+pub const TREE_DEPTH: usize = 64;
+
+/// A 32-byte BLAKE2b digest.
+pub type Digest = [u8; 32];
+
+const LEAF_PERSONALIZATION: &[u8; 16] = b"SimTFL_Leaf_Hash";
+const NODE_PERSONALIZATION: &[u8; 16] = b"SimTFL_SMT_Node_";
+const ROOT_PERSONALIZATION: &[u8; 16] = b"SimTFL_StateRoot";
+
+fn blake2b(personalization: &[u8; 16]) -> blake2b_simd::State {
+    blake2b_simd::Params::new()
+        .hash_length(32)
+        .personal(personalization)
+        .to_state()
+}
+
+fn finalize(state: &blake2b_simd::State) -> Digest {
+    let mut digest = [0u8; 32];
+    digest.copy_from_slice(state.finalize().as_bytes());
+    digest
+}
+
+/// A value with an explicit byte encoding, used to digest it for commitments.
+///
+/// The encoding does not depend on the platform: integers are little-endian at a fixed
+/// width (`usize` as 64 bits), and sequences are prefixed with their length.
+pub trait Encode {
+    /// Appends the encoding of the value to `out`.
+    fn encode(&self, out: &mut Vec<u8>);
+}
+
+macro_rules! impl_encode_int {
+    ($($int:ty),*) => {
+        $(impl Encode for $int {
+            fn encode(&self, out: &mut Vec<u8>) {
+                out.extend_from_slice(&self.to_le_bytes());
+            }
+        })*
+    };
+}
+
+impl_encode_int!(u8, u32, u64, i32, i64);
+
+impl Encode for usize {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (*self as u64).encode(out);
+    }
+}
+
+impl Encode for bool {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (*self as u8).encode(out);
+    }
+}
+
+impl Encode for Digest {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(self);
+    }
+}
+
+impl<T: Encode + ?Sized> Encode for &T {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (**self).encode(out);
+    }
+}
+
+impl<T: Encode> Encode for Option<T> {
+    fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            None => 0u8.encode(out),
+            Some(value) => {
+                1u8.encode(out);
+                value.encode(out);
+            }
+        }
+    }
+}
+
+impl<T: Encode> Encode for [T] {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.len().encode(out);
+        for item in self {
+            item.encode(out);
+        }
+    }
+}
+
+impl<T: Encode> Encode for Vec<T> {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.as_slice().encode(out);
+    }
+}
+
+impl<A: Encode, B: Encode> Encode for (A, B) {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.0.encode(out);
+        self.1.encode(out);
+    }
+}
+
+impl<A: Encode, B: Encode, C: Encode> Encode for (A, B, C) {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.0.encode(out);
+        self.1.encode(out);
+        self.2.encode(out);
+    }
+}
+
+/// Returns the BLAKE2b digest of the encoding of a value.
+pub fn digest<T: Encode + ?Sized>(value: &T) -> Digest {
+    let mut bytes = Vec::new();
+    value.encode(&mut bytes);
+    let mut state = blake2b(LEAF_PERSONALIZATION);
+    state.update(&bytes);
+    finalize(&state)
+}
+
+/// Hashes two child nodes into their parent.
+fn hash_node(left: &Digest, right: &Digest) -> Digest {
+    let mut state = blake2b(NODE_PERSONALIZATION);
+    state.update(left);
+    state.update(right);
+    finalize(&state)
+}
+
+/// Returns the roots of empty subtrees, indexed by level (0 is an empty leaf).
+fn empty_roots() -> &'static [Digest; TREE_DEPTH + 1] {
+    static EMPTY_ROOTS: OnceLock<[Digest; TREE_DEPTH + 1]> = OnceLock::new();
+    EMPTY_ROOTS.get_or_init(|| {
+        let mut roots = [[0u8; 32]; TREE_DEPTH + 1];
+        for level in 0..TREE_DEPTH {
+            roots[level + 1] = hash_node(&roots[level], &roots[level]);
+        }
+        roots
+    })
+}
+
+/// Returns the tree key of a leaf digest.
+fn key_of(leaf: &Digest) -> u64 {
+    u64::from_le_bytes(leaf[..8].try_into().unwrap())
+}
+
+/// Returns the index at `level` of the node on the path to `key`.
+fn index_at(key: u64, level: usize) -> u64 {
+    key.checked_shr(level as u32).unwrap_or(0)
+}
+
+/// A sparse Merkle tree over a set of digests.
+///
+/// Only nodes that differ from the root of an empty subtree are stored. Two elements
+/// whose digests share their first 64 bits would occupy the same leaf; this is
+/// negligible for simulation-sized sets.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Eq, PartialEq)]
+pub struct SparseMerkleTree {
+    nodes: HashMap<(u8, u64), Digest>,
+    len: usize,
+}
+
+impl SparseMerkleTree {
+    /// Creates an empty tree.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of elements in the tree.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Checks if the tree is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn node(&self, level: usize, index: u64) -> Digest {
+        self.nodes
+            .get(&(level as u8, index))
+            .copied()
+            .unwrap_or(empty_roots()[level])
+    }
+
+    /// Returns the root of the tree.
+    pub fn root(&self) -> Digest {
+        self.node(TREE_DEPTH, 0)
+    }
+
+    /// Checks if the tree contains an element digest.
+    pub fn contains(&self, leaf: &Digest) -> bool {
+        self.nodes.get(&(0, key_of(leaf))) == Some(leaf)
+    }
+
+    /// Inserts an element digest.
+    ///
+    /// ## Returns
+    /// `true` if the element was not already present.
+    pub fn insert(&mut self, leaf: Digest) -> bool {
+        if self.contains(&leaf) {
+            return false;
+        }
+        self.len += 1;
+        self.update(key_of(&leaf), leaf);
+        true
+    }
+
+    /// Removes an element digest.
+    ///
+    /// ## Returns
+    /// `true` if the element was present.
+    pub fn remove(&mut self, leaf: &Digest) -> bool {
+        if !self.contains(leaf) {
+            return false;
+        }
+        self.len -= 1;
+        self.update(key_of(leaf), empty_roots()[0]);
+        true
+    }
+
+    /// Sets a leaf and recomputes the path from it to the root.
+    fn update(&mut self, key: u64, leaf: Digest) {
+        let mut value = leaf;
+        for level in 0..=TREE_DEPTH {
+            let index = index_at(key, level);
+            if value == empty_roots()[level] {
+                self.nodes.remove(&(level as u8, index));
+            } else {
+                self.nodes.insert((level as u8, index), value);
+            }
+            if level == TREE_DEPTH {
+                break;
+            }
+            let (left, right) = (self.node(level, index & !1), self.node(level, index | 1));
+            value = hash_node(&left, &right);
+        }
+    }
+
+    /// Returns a proof for the leaf at the position of an element digest.
+    ///
+    /// The same proof shows membership if the element is in the tree, and
+    /// non-membership (an empty leaf) otherwise.
+    pub fn prove(&self, leaf: &Digest) -> MerkleProof {
+        let key = key_of(leaf);
+        let siblings = (0..TREE_DEPTH)
+            .map(|level| self.node(level, index_at(key, level) ^ 1))
+            .collect();
+        MerkleProof { key, siblings }
+    }
+}
+
+/// A Merkle path from a leaf position to the root of a [`SparseMerkleTree`].
+///
+/// ## Fields
+/// - `key`: The position of the leaf.
+/// - `siblings`: The sibling of each node on the path, from the leaf level upwards.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Eq, PartialEq)]
+pub struct MerkleProof {
+    pub key: u64,
+    pub siblings: Vec<Digest>,
+}
+
+impl MerkleProof {
+    /// Computes the root implied by this path for the given leaf value.
+    pub fn root(&self, leaf: &Digest) -> Digest {
+        self.siblings.iter().enumerate().fold(*leaf, |node, (level, sibling)| {
+            if index_at(self.key, level) & 1 == 0 {
+                hash_node(&node, sibling)
+            } else {
+                hash_node(sibling, &node)
+            }
+        })
+    }
+
+    /// Checks that an element digest is in the tree with the given root.
+    pub fn verify_membership(&self, root: &Digest, leaf: &Digest) -> bool {
+        self.key == key_of(leaf) && self.siblings.len() == TREE_DEPTH && self.root(leaf) == *root
+    }
+
+    /// Checks that an element digest is not in the tree with the given root.
+    pub fn verify_non_membership(&self, root: &Digest, leaf: &Digest) -> bool {
+        self.key == key_of(leaf)
+            && self.siblings.len() == TREE_DEPTH
+            && self.root(&empty_roots()[0]) == *root
+    }
+}
+
+/// A commitment to the state of a best-chain context.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[derive(Eq, Hash, PartialEq)]
+pub struct StateRoot(pub Digest);
+
+impl Encode for StateRoot {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.0.encode(out);
+    }
+}
+
+/// The incrementally maintained components of a [`StateRoot`].
+///
+/// ## Fields
+/// - `utxos`: Tree of unspent transparent outputs.
+/// - `notes`: Tree of all shielded notes ever created.
+/// - `nullifiers`: Tree of spent shielded notes.
+/// - `transparent_pool`: Total value of unspent transparent outputs.
+/// - `shielded_pool`: Total value of unspent shielded notes.
+/// - `total_issuance`: Total value issued.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Eq, PartialEq)]
+pub struct StateCommitment {
+    pub utxos: SparseMerkleTree,
+    pub notes: SparseMerkleTree,
+    pub nullifiers: SparseMerkleTree,
+    pub transparent_pool: i64,
+    pub shielded_pool: i64,
+    pub total_issuance: i64,
+}
+
+impl StateCommitment {
+    /// Creates the commitment to an empty state.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the state root.
+    pub fn root(&self) -> StateRoot {
+        StateRoot::from_parts(
+            &self.utxos.root(),
+            &self.notes.root(),
+            &self.nullifiers.root(),
+            [self.transparent_pool, self.shielded_pool, self.total_issuance],
+        )
+    }
+}
+
+impl StateRoot {
+    /// Hashes the tree roots and pool balances into a state root.
+    ///
+    /// ## Parameters
+    /// - `balances`: The transparent pool, shielded pool and total issuance, in that order.
+    pub fn from_parts(utxos: &Digest, notes: &Digest, nullifiers: &Digest, balances: [i64; 3]) -> Self {
+        let mut state = blake2b(ROOT_PERSONALIZATION);
+        state.update(utxos);
+        state.update(notes);
+        state.update(nullifiers);
+        for balance in balances {
+            state.update(&balance.to_le_bytes());
+        }
+        StateRoot(finalize(&state))
+    }
+}
@@ -11,6 +11,11 @@
 use serde::{Serialize, Deserialize};
 use std::collections::{HashSet, HashMap};
 use std::hash::Hash;
+use crate::commitment::{self, Digest, Encode, StateCommitment, StateRoot};
+use crate::traits::ContextTrait;
+use crate::transaction::BCTransaction;
+use crate::validation::{self, ValidationError};
+use crate::amount::Amount;
 
 /// Context for a best-chain protocol.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,7 +28,15 @@ pub struct BCContext {
     /// Notes and their spentness status
     pub notes: HashMap<super::transaction::Note, Spentness>,
     /// Total issuance
-    pub total_issuance: i32,
+    pub total_issuance: Amount,
+    /// Height of the block that transactions are currently being added to
+    pub height: u32,
+    /// Consensus parameters deciding which rules apply at each height
+    pub params: super::params::ConsensusParams,
+    /// Unspent coinbase outputs and the height at which they were mined
+    pub coinbase_outputs: HashMap<super::transaction::TXO, u32>,
+    /// Authenticated commitment to the UTXO set, notes, nullifiers and pool balances
+    pub commitment: StateCommitment,
 }
 
 impl Default for BCContext {
@@ -33,28 +46,159 @@ impl Default for BCContext {
 }
 
 impl BCContext {
-    /// Create a new `BCContext`.
+    /// Create a new `BCContext` using test network parameters.
+    /// 
+    /// See [`ConsensusParams::new`](super::params::ConsensusParams::new).
     pub fn new() -> Self {
+        Self::with_params(super::params::ConsensusParams::new())
+    }
+
+    /// Create a new `BCContext` with the given consensus parameters.
+    pub fn with_params(params: super::params::ConsensusParams) -> Self {
         BCContext {
             transactions: Vec::new(),
             utxo_set: HashSet::new(),
             notes: HashMap::new(),
-            total_issuance: 0,
+            total_issuance: Amount::ZERO,
+            height: 0,
+            params,
+            coinbase_outputs: HashMap::new(),
+            commitment: StateCommitment::new(),
         }
     }
 
     pub fn add_transaction(&mut self, tx: super::transaction::BCTransaction) -> bool {
-        if !tx.is_valid(self) {
-            return false;
+        validation::add_transaction(self, tx)
+    }
+
+    /// Returns the consensus rules in effect at the current height.
+    pub fn rules(&self) -> super::params::ConsensusRules {
+        self.params.rules_at(self.height)
+    }
+
+    /// Adds all transactions of a block to the context at the current height.
+    /// 
+    /// The block is validated against the rules in effect at the current height
+    /// and applied atomically: if the block or any of its transactions is invalid
+    /// the context is left unchanged. On success the height is advanced so that
+    /// subsequent transactions belong to the next block.
+    /// 
+    /// ## Returns
+    /// - `true` if every transaction in the block was added.
+    /// - `false` otherwise.
+    pub fn add_block(&mut self, block: &super::block::BCBlock) -> bool {
+        validation::add_block(self, block)
+    }
+
+    /// Returns the commitment to the current state.
+    /// 
+    /// Two contexts hold the same UTXO set, notes, nullifiers and pool balances
+    /// exactly when their state roots are equal.
+    pub fn state_root(&self) -> StateRoot {
+        self.commitment.root()
+    }
+
+    /// Check if a transparent output may be spent at the current height.
+    /// 
+    /// Only coinbase outputs are subject to maturity; any other output is always mature.
+    pub fn is_mature(&self, txo: &super::transaction::TXO) -> bool {
+        self.coinbase_outputs
+            .get(txo)
+            .is_none_or(|mined| self.height >= mined + self.rules().coinbase_maturity)
+    }
+
+    /// Check if all notes in the given slice are unspent.
+    pub fn can_spend(&self, notes: &[super::transaction::Note]) -> bool {
+        notes.iter().all(|note| {
+            self.notes.get(note).is_some_and(|spent| *spent == Spentness::Unspent)
+        })
+    }
+
+    /// Check if a note is spent.
+    pub fn is_spent(&self, note: &super::transaction::Note) -> bool {
+        self.notes.get(note).is_some_and(|s| *s == Spentness::Spent)
+    }
+
+    /// Copy the context (for forks).
+    pub fn copy(&self) -> Self {
+        Self {
+            transactions: self.transactions.clone(),
+            utxo_set: self.utxo_set.clone(),
+            notes: self.notes.clone(),
+            total_issuance: self.total_issuance,
+            height: self.height,
+            params: self.params.clone(),
+            coinbase_outputs: self.coinbase_outputs.clone(),
+            commitment: self.commitment.clone(),
+        }
+    }
+}
+
+impl ContextTrait for BCContext {
+    type Transaction = BCTransaction;
+
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn rules(&self) -> super::params::ConsensusRules {
+        BCContext::rules(self)
+    }
+
+    fn total_issuance(&self) -> Amount {
+        self.total_issuance
+    }
+
+    fn check_transaction(&self, tx: &BCTransaction) -> Result<(), ValidationError> {
+        // Check transparent inputs
+        for txo in &tx.transparent_inputs {
+            if !self.utxo_set.contains(txo) {
+                return Err(ValidationError::MissingInput);
+            }
+            if !self.is_mature(txo) {
+                return Err(ValidationError::ImmatureCoinbase);
+            }
+        }
+
+        // Check shielded inputs
+        for note in &tx.shielded_inputs {
+            if self.notes.get(note) != Some(&Spentness::Unspent) {
+                return Err(ValidationError::NoteUnavailable);
+            }
+        }
+
+        // Check shielded outputs, which must not re-create a note, spent or not
+        for note in &tx.shielded_outputs {
+            if self.notes.contains_key(note) {
+                return Err(ValidationError::DuplicateNote);
+            }
         }
 
+        Ok(())
+    }
+
+    fn apply_transaction(&mut self, tx: BCTransaction) {
         // Update UTXO set
         for txo in &tx.transparent_inputs {
             self.utxo_set.remove(txo);
+            self.coinbase_outputs.remove(txo);
+            if self.commitment.utxos.remove(&commitment::digest(txo)) {
+                self.commitment.transparent_pool -= txo.value.zatoshi();
+            }
         }
 
         for txo in &tx.transparent_outputs {
             self.utxo_set.insert(txo.clone());
+            if self.commitment.utxos.insert(commitment::digest(txo)) {
+                self.commitment.transparent_pool += txo.value.zatoshi();
+            }
+        }
+
+        // Track coinbase outputs until they are spent
+        if tx.is_coinbase() {
+            for txo in &tx.transparent_outputs {
+                self.coinbase_outputs.insert(txo.clone(), self.height);
+            }
         }
 
         // Update shielded notes
@@ -62,49 +206,66 @@ impl BCContext {
             if let Some(entry) = self.notes.get_mut(note) {
                 *entry = Spentness::Spent;
             }
+            if self.commitment.nullifiers.insert(commitment::digest(note)) {
+                self.commitment.shielded_pool -= note.value.zatoshi();
+            }
         }
 
         for note in &tx.shielded_outputs {
             self.notes.insert(note.clone(), Spentness::Unspent);
+            if self.commitment.notes.insert(commitment::digest(note)) {
+                self.commitment.shielded_pool += note.value.zatoshi();
+            }
         }
 
         // Update total issuance
-        self.total_issuance += tx.issuance;
+        self.total_issuance = (self.total_issuance + tx.issuance)
+            .expect("total issuance is range-checked during validation");
+        self.commitment.total_issuance += tx.issuance.zatoshi();
 
         // Add the transaction to the list
         self.transactions.push(tx);
+    }
 
-        true
+    fn advance_height(&mut self) {
+        self.height += 1;
     }
 
-    /// Check if all notes in the given slice are unspent.
-    pub fn can_spend(&self, notes: &[super::transaction::Note]) -> bool {
-        notes.iter().all(|note| {
-            self.notes.get(note).is_some_and(|spent| *spent == Spentness::Unspent)
-        })
+    fn copy(&self) -> Self {
+        BCContext::copy(self)
     }
 
-    /// Check if a note is spent.
-    pub fn is_spent(&self, note: &super::transaction::Note) -> bool {
-        self.notes.get(note).is_some_and(|s| *s == Spentness::Spent)
+    fn state_root(&self) -> StateRoot {
+        BCContext::state_root(self)
     }
+}
 
-    /// Copy the context (for forks).
-    pub fn copy(&self) -> Self {
-        Self {
-            transactions: self.transactions.clone(),
-            utxo_set: self.utxo_set.clone(),
-            notes: self.notes.clone(),
-            total_issuance: self.total_issuance,
-        }
+impl BCContext {
+    /// Returns the digests of the unspent coinbase outputs with their mined heights, sorted.
+    fn coinbase_digests(&self) -> Vec<Digest> {
+        let mut digests: Vec<_> = self
+            .coinbase_outputs
+            .iter()
+            .map(|(txo, mined)| commitment::digest(&(txo, *mined)))
+            .collect();
+        digests.sort();
+        digests
+    }
+}
+
+impl Encode for BCContext {
+    fn encode(&self, out: &mut Vec<u8>) {
+        // The state root covers the UTXO set, notes, nullifiers and pool balances; the height
+        // and coinbase heights also decide validity, through expiry and maturity.
+        self.state_root().encode(out);
+        self.height.encode(out);
+        self.coinbase_digests().encode(out);
     }
 }
 
 impl Hash for BCContext {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        // Implement the hash function for BCContext
-        // For simplicity, hash the total_issuance field
-        self.total_issuance.hash(state);
+        commitment::digest(self).hash(state);
     }
 }
 
@@ -0,0 +1,85 @@
+//! # Fees
+//!
+//! This module implements the ZIP-317 conventional fee for Best-Chain transactions.
+//!
+//! The conventional fee charges a marginal fee per logical action, with a grace allowance
+//! so that small transactions pay a flat minimum. Transactions paying less than the
+//! conventional fee have "unpaid actions", which a [`FeePolicy`] may refuse to relay.
+
+use crate::amount::{Amount, MAX_MONEY};
+use crate::transaction::BCTransaction;
+
+/// Fee charged per logical action.
+pub const MARGINAL_FEE: Amount = Amount::const_from_i64(5_000);
+
+/// Number of logical actions every transaction is charged for at minimum.
+pub const GRACE_ACTIONS: usize = 2;
+
+/// Returns the number of logical actions of a transaction.
+///
+/// Every transparent input and output is treated as a standard P2PKH input or output,
+/// so the transparent contribution is `max(inputs, outputs)`. Shielded spends and
+/// outputs are paired into actions the same way.
+pub fn logical_actions(tx: &BCTransaction) -> usize {
+    let transparent = tx.transparent_inputs.len().max(tx.transparent_outputs.len());
+    let shielded = tx.shielded_inputs.len().max(tx.shielded_outputs.len());
+    transparent + shielded
+}
+
+/// Returns the ZIP-317 conventional fee of a transaction.
+pub fn conventional_fee(tx: &BCTransaction) -> Amount {
+    FeePolicy::new().conventional_fee(tx)
+}
+
+/// Policy deciding which transactions are admitted to a mempool.
+///
+/// ## Fields
+/// - `marginal_fee`: Fee charged per logical action.
+/// - `grace_actions`: Number of logical actions every transaction is charged for at minimum.
+/// - `max_unpaid_actions`: Number of logical actions a transaction may leave unpaid and still be admitted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeePolicy {
+    pub marginal_fee: Amount,
+    pub grace_actions: usize,
+    pub max_unpaid_actions: usize,
+}
+
+impl Default for FeePolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FeePolicy {
+    /// Creates the ZIP-317 policy, which requires the full conventional fee.
+    pub fn new() -> Self {
+        Self {
+            marginal_fee: MARGINAL_FEE,
+            grace_actions: GRACE_ACTIONS,
+            max_unpaid_actions: 0,
+        }
+    }
+
+    /// Returns the conventional fee of a transaction under this policy.
+    ///
+    /// The fee saturates at `MAX_MONEY` for absurdly large transactions.
+    pub fn conventional_fee(&self, tx: &BCTransaction) -> Amount {
+        let actions = logical_actions(tx).max(self.grace_actions) as i64;
+        let fee = self.marginal_fee.zatoshi().saturating_mul(actions).min(MAX_MONEY);
+        Amount::const_from_i64(fee)
+    }
+
+    /// Returns the number of logical actions not covered by the transaction's fee.
+    pub fn unpaid_actions(&self, tx: &BCTransaction) -> usize {
+        let charged = logical_actions(tx).max(self.grace_actions);
+        let paid = (tx.fee.zatoshi().max(0) / self.marginal_fee.zatoshi().max(1)) as usize;
+        charged.saturating_sub(paid)
+    }
+
+    /// Checks if the policy admits a transaction.
+    ///
+    /// Coinbase transactions are never admitted, since they are only valid in a block.
+    pub fn admits(&self, tx: &BCTransaction) -> bool {
+        !tx.is_coinbase() && self.unpaid_actions(tx) <= self.max_unpaid_actions
+    }
+}
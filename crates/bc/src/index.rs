@@ -0,0 +1,235 @@
+//! # Chain Index
+//!
+//! This module implements indexes over the best chain for fast lookups: transactions by
+//! id, the block containing a transaction, the transaction spending an outpoint, and the
+//! unspent outputs paying to an address.
+//!
+//! A [`ChainIndex`] is updated by connecting and disconnecting blocks in chain order.
+//! [`IndexedChain`] pairs it with a [`BlockTree`] and keeps the two consistent across
+//! reorganizations.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use crate::amount::Amount;
+use crate::block::{BCBlock, BlockHash};
+use crate::chain::BlockTree;
+use crate::context::BCContext;
+use crate::transaction::{Address, BCTransaction, OutPoint, TxId, TXO};
+
+/// Position of a transaction in the best chain.
+///
+/// ## Fields
+/// - `block`: The hash of the block containing the transaction.
+/// - `height`: The height of that block.
+/// - `position`: The index of the transaction in the block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TxLocation {
+    pub block: BlockHash,
+    pub height: u32,
+    pub position: usize,
+}
+
+/// Indexes over the transactions and outputs of the connected blocks.
+///
+/// Outputs are identified by the outpoint they get when their block is connected, from the
+/// id of the transaction creating them and their position among its transparent outputs.
+/// An input refers to the unspent output it spends by value; the ledger never holds two equal
+/// unspent outputs, so the input is resolved to the outpoint of that output.
+/// Blocks must be disconnected in the reverse order they were connected.
+///
+/// ## Fields
+/// - `transactions`: Connected transactions and their locations, by id.
+/// - `outputs`: The outputs created by connected blocks, spent or not, by outpoint.
+/// - `spenders`: The transaction spending each spent outpoint.
+/// - `utxos`: Unspent outputs created by connected blocks, by outpoint.
+/// - `addresses`: Outpoints of the unspent outputs paying to each address.
+/// - `undo`: The outputs spent by each connected block, by outpoint.
+#[derive(Debug, Clone, Default)]
+pub struct ChainIndex {
+    pub transactions: HashMap<TxId, (BCTransaction, TxLocation)>,
+    pub outputs: HashMap<OutPoint, TXO>,
+    pub spenders: HashMap<OutPoint, TxId>,
+    pub utxos: HashMap<OutPoint, TXO>,
+    pub addresses: HashMap<Address, BTreeMap<OutPoint, Amount>>,
+    pub undo: HashMap<BlockHash, Vec<(OutPoint, TXO)>>,
+}
+
+impl ChainIndex {
+    /// Creates an empty index.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the transactions of a block appended to the chain.
+    ///
+    /// ## Parameters
+    /// - `block`: The block being connected.
+    /// - `height`: The height of the block.
+    pub fn connect_block(&mut self, block: &BCBlock, height: u32) {
+        let mut spent = Vec::new();
+        for (position, tx) in block.transactions.iter().enumerate() {
+            let txid = tx.txid();
+            for txo in &tx.transparent_inputs {
+                // Outputs created outside the connected blocks have no outpoint to index.
+                let Some(outpoint) = self.outpoint(txo) else {
+                    continue;
+                };
+                self.spenders.insert(outpoint, txid);
+                if let Some(txo) = self.remove_utxo(&outpoint) {
+                    spent.push((outpoint, txo));
+                }
+            }
+            for (index, txo) in tx.transparent_outputs.iter().enumerate() {
+                let outpoint = OutPoint { txid, index };
+                self.outputs.insert(outpoint, txo.clone());
+                self.insert_utxo(outpoint, txo.clone());
+            }
+            let location = TxLocation { block: block.hash, height, position };
+            self.transactions.insert(txid, (tx.clone(), location));
+        }
+        self.undo.insert(block.hash, spent);
+    }
+
+    /// Removes the transactions of the block at the tip of the chain.
+    ///
+    /// Outputs spent by the block become unspent again, except those the block created itself.
+    ///
+    /// ## Parameters
+    /// - `block`: The block being disconnected.
+    pub fn disconnect_block(&mut self, block: &BCBlock) {
+        let mut created = HashSet::new();
+        for tx in block.transactions.iter().rev() {
+            let txid = tx.txid();
+            created.insert(txid);
+            if self.transactions.get(&txid).is_some_and(|(_, location)| location.block == block.hash) {
+                self.transactions.remove(&txid);
+            }
+            for index in 0..tx.transparent_outputs.len() {
+                let outpoint = OutPoint { txid, index };
+                self.remove_utxo(&outpoint);
+                self.outputs.remove(&outpoint);
+            }
+        }
+        for (outpoint, txo) in self.undo.remove(&block.hash).unwrap_or_default() {
+            self.spenders.remove(&outpoint);
+            if !created.contains(&outpoint.txid) {
+                self.insert_utxo(outpoint, txo);
+            }
+        }
+    }
+
+    fn insert_utxo(&mut self, outpoint: OutPoint, txo: TXO) {
+        self.addresses.entry(txo.address).or_default().insert(outpoint, txo.value);
+        self.utxos.insert(outpoint, txo);
+    }
+
+    fn remove_utxo(&mut self, outpoint: &OutPoint) -> Option<TXO> {
+        let txo = self.utxos.remove(outpoint)?;
+        if let Some(outpoints) = self.addresses.get_mut(&txo.address) {
+            outpoints.remove(outpoint);
+            if outpoints.is_empty() {
+                self.addresses.remove(&txo.address);
+            }
+        }
+        Some(txo)
+    }
+
+    /// Returns the outpoint of the unspent output equal to `txo`, if a connected block created it.
+    pub fn outpoint(&self, txo: &TXO) -> Option<OutPoint> {
+        self.addresses
+            .get(&txo.address)?
+            .keys()
+            .find(|outpoint| self.utxos.get(outpoint) == Some(txo))
+            .copied()
+    }
+
+    /// Returns the output at an outpoint, spent or not, if a connected block created it.
+    pub fn output(&self, outpoint: &OutPoint) -> Option<&TXO> {
+        self.outputs.get(outpoint)
+    }
+
+    /// Returns the connected transaction with the given id.
+    pub fn transaction(&self, txid: &TxId) -> Option<&BCTransaction> {
+        self.transactions.get(txid).map(|(tx, _)| tx)
+    }
+
+    /// Returns the location of the connected transaction with the given id.
+    pub fn location(&self, txid: &TxId) -> Option<TxLocation> {
+        self.transactions.get(txid).map(|(_, location)| *location)
+    }
+
+    /// Returns the hash of the block containing the transaction with the given id.
+    pub fn containing_block(&self, txid: &TxId) -> Option<BlockHash> {
+        self.location(txid).map(|location| location.block)
+    }
+
+    /// Returns the id of the transaction spending an outpoint, if it is spent.
+    pub fn spender(&self, outpoint: &OutPoint) -> Option<TxId> {
+        self.spenders.get(outpoint).copied()
+    }
+
+    /// Returns the unspent outputs paying to an address, ordered by outpoint.
+    pub fn utxos_by_address(&self, address: &Address) -> Vec<&TXO> {
+        self.addresses
+            .get(address)
+            .map_or_else(Vec::new, |outpoints| outpoints.keys().map(|outpoint| &self.utxos[outpoint]).collect())
+    }
+
+    /// Returns the total value of the unspent outputs paying to an address,
+    /// or `None` if it is out of range.
+    pub fn balance(&self, address: &Address) -> Option<Amount> {
+        self.addresses.get(address).map_or(Some(Amount::ZERO), |outpoints| outpoints.values().sum())
+    }
+}
+
+/// A block tree whose best chain is indexed.
+///
+/// ## Fields
+/// - `tree`: The block tree.
+/// - `index`: The index over the blocks of the best chain.
+#[derive(Debug, Clone)]
+pub struct IndexedChain {
+    pub tree: BlockTree<BCContext>,
+    pub index: ChainIndex,
+}
+
+impl IndexedChain {
+    /// Creates an empty indexed chain.
+    ///
+    /// ## Parameters
+    /// - `initial_context`: The context the genesis block is applied to.
+    pub fn new(initial_context: BCContext) -> Self {
+        Self {
+            tree: BlockTree::new(initial_context),
+            index: ChainIndex::new(),
+        }
+    }
+
+    /// Adds a block to the tree, updating the index if the best chain changes.
+    ///
+    /// When the block causes a reorganization, the blocks leaving the best chain are
+    /// disconnected before those joining it are connected.
+    ///
+    /// ## Returns
+    /// - `true` if the block was added to the tree.
+    /// - `false` otherwise.
+    pub fn add_block(&mut self, block: BCBlock) -> bool {
+        let old_tip = self.tree.tip;
+        if !self.tree.add_block(block) {
+            return false;
+        }
+        let new_tip = match self.tree.tip {
+            Some(tip) if Some(tip) != old_tip => tip,
+            _ => return true,
+        };
+
+        let (disconnect, connect) = self.tree.reorg_path(old_tip.as_ref(), &new_tip);
+        for hash in disconnect {
+            self.index.disconnect_block(&self.tree.blocks[&hash].block);
+        }
+        for hash in connect {
+            let entry = &self.tree.blocks[&hash];
+            self.index.connect_block(&entry.block, entry.height);
+        }
+        true
+    }
+}
@@ -7,11 +7,39 @@
 //! - Transaction validation
 //! - Block creation
 //! - Context management
+//! - Versioned consensus rules with network upgrade activation heights
+//! - ZIP-317 conventional fees, mempool admission and block size limits
+//! - First-seen and fee-bump transaction replacement in the mempool
+//! - Validation and block trees generic over the transaction model (see [`traits`])
+//! - Authenticated state roots over the UTXO set, notes, nullifiers and pool balances
+//! - Checked 64-bit amounts bounded by `MAX_MONEY`
+//! - Indexed lookups by transaction id, outpoint and address
+//! - Parallel context-free transaction checks with rayon (see `benches/validation.rs`)
+//! - A header-chain light client verifying transaction and UTXO inclusion proofs
+//! - Chain growth, chain quality, orphan rate and reorg depth metrics with CSV export
+//! - Poisson and trace-driven (CSV) block arrival times
+//! - Anonymity sets of shielded spends by anchor
+//! - A slot-based proof-of-stake longest-chain protocol (Ouroboros Praos style)
+//! - Shielded spam and congestion scenarios
 
 pub mod transaction;
 pub mod block;
 pub mod context;
 pub mod traits;
+pub mod params;
+pub mod fees;
+pub mod mempool;
+pub mod validation;
+pub mod chain;
+pub mod commitment;
+pub mod amount;
+pub mod index;
+pub mod light;
+pub mod metrics;
+pub mod arrivals;
+pub mod anonymity;
+pub mod pos;
+pub mod spam;
 
 /// Initialize logging (if needed).
 pub fn init_logging() {
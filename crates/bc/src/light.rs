@@ -0,0 +1,277 @@
+//! # Light Client
+//!
+//! This module implements a light client that follows only block headers.
+//!
+//! A [`BlockHeader`] commits to the block's parent, its score (the work it adds), the
+//! state root after the block and the root of a Merkle tree over its transaction ids.
+//! The [`LightClient`] selects the best header chain by total work, and verifies
+//! inclusion proofs produced by a full node: [`TransactionProof`]s against a header's
+//! transaction root, and [`UtxoProof`]s against its state root.
+
+use std::collections::HashMap;
+use crate::block::{BCBlock, BlockHash};
+use crate::chain::{fork_choice, BlockEntry, ChainWork};
+use crate::commitment::{self, Digest, MerkleProof, SparseMerkleTree, StateRoot};
+use crate::context::BCContext;
+use crate::transaction::{BCTransaction, TxId, TXO};
+
+/// The header of a best-chain block.
+///
+/// ## Fields
+/// - `hash`: The hash of the block.
+/// - `parent`: The hash of the parent block, or `None` for the genesis block.
+/// - `score`: The work the block adds to its chain.
+/// - `state_root`: The state root after applying the block.
+/// - `tx_root`: The root of the tree of the block's transaction ids.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockHeader {
+    pub hash: BlockHash,
+    pub parent: Option<BlockHash>,
+    pub score: i32,
+    pub state_root: StateRoot,
+    pub tx_root: Digest,
+}
+
+impl BlockHeader {
+    /// Returns the header of a block in a full node's block tree.
+    pub fn from_entry(entry: &BlockEntry<BCContext>) -> Self {
+        Self {
+            hash: entry.block.hash,
+            parent: entry.block.parent,
+            score: entry.block.score,
+            state_root: entry.context.state_root(),
+            tx_root: transaction_tree(&entry.block).root(),
+        }
+    }
+}
+
+/// Returns the tree of a block's transaction ids.
+fn transaction_tree(block: &BCBlock) -> SparseMerkleTree {
+    let mut tree = SparseMerkleTree::new();
+    for tx in &block.transactions {
+        tree.insert(tx.txid().0);
+    }
+    tree
+}
+
+/// Proof that a transaction is included in a block.
+///
+/// ## Fields
+/// - `block`: The hash of the block containing the transaction.
+/// - `txid`: The id of the transaction.
+/// - `path`: The Merkle path from the transaction id to the block's transaction root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionProof {
+    pub block: BlockHash,
+    pub txid: TxId,
+    pub path: MerkleProof,
+}
+
+impl TransactionProof {
+    /// Creates a proof that a transaction is included in a block.
+    ///
+    /// ## Returns
+    /// `None` if the transaction is not in the block.
+    pub fn new(block: &BCBlock, tx: &BCTransaction) -> Option<Self> {
+        let txid = tx.txid();
+        let tree = transaction_tree(block);
+        tree.contains(&txid.0).then(|| Self {
+            block: block.hash,
+            txid,
+            path: tree.prove(&txid.0),
+        })
+    }
+
+    /// Checks the proof against a header.
+    pub fn verify(&self, header: &BlockHeader) -> bool {
+        header.hash == self.block && self.path.verify_membership(&header.tx_root, &self.txid.0)
+    }
+}
+
+/// Proof that a transparent output is, or is not, unspent in the state after a block.
+///
+/// The proof opens the state root into its components, so that the UTXO tree root
+/// can be checked against it.
+///
+/// ## Fields
+/// - `block`: The hash of the block whose state the proof refers to.
+/// - `utxos`: The root of the UTXO tree.
+/// - `notes`: The root of the note tree.
+/// - `nullifiers`: The root of the nullifier tree.
+/// - `balances`: The transparent pool, shielded pool and total issuance.
+/// - `path`: The Merkle path from the output's position to the UTXO tree root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UtxoProof {
+    pub block: BlockHash,
+    pub utxos: Digest,
+    pub notes: Digest,
+    pub nullifiers: Digest,
+    pub balances: [i64; 3],
+    pub path: MerkleProof,
+}
+
+impl UtxoProof {
+    /// Creates a proof for an output against the state after a block.
+    pub fn new(entry: &BlockEntry<BCContext>, txo: &TXO) -> Self {
+        let state = &entry.context.commitment;
+        Self {
+            block: entry.block.hash,
+            utxos: state.utxos.root(),
+            notes: state.notes.root(),
+            nullifiers: state.nullifiers.root(),
+            balances: [state.transparent_pool, state.shielded_pool, state.total_issuance],
+            path: state.utxos.prove(&commitment::digest(txo)),
+        }
+    }
+
+    /// Checks that the opening matches the header's state root.
+    fn opens(&self, header: &BlockHeader) -> bool {
+        header.hash == self.block
+            && StateRoot::from_parts(&self.utxos, &self.notes, &self.nullifiers, self.balances) == header.state_root
+    }
+
+    /// Checks that the output is unspent in the state committed to by a header.
+    pub fn verify_unspent(&self, header: &BlockHeader, txo: &TXO) -> bool {
+        self.opens(header) && self.path.verify_membership(&self.utxos, &commitment::digest(txo))
+    }
+
+    /// Checks that the output is not unspent in the state committed to by a header.
+    pub fn verify_not_unspent(&self, header: &BlockHeader, txo: &TXO) -> bool {
+        self.opens(header) && self.path.verify_non_membership(&self.utxos, &commitment::digest(txo))
+    }
+}
+
+/// A header together with its position in the header tree.
+///
+/// ## Fields
+/// - `header`: The header itself.
+/// - `height`: The height of the block (the genesis block has height 0).
+/// - `total_work`: The sum of the scores of the block and all its ancestors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeaderEntry {
+    pub header: BlockHeader,
+    pub height: u32,
+    pub total_work: i64,
+}
+
+impl ChainWork for HeaderEntry {
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn total_work(&self) -> i64 {
+        self.total_work
+    }
+}
+
+/// A client that follows the header chain with the most work.
+///
+/// ## Fields
+/// - `headers`: All headers accepted, by block hash.
+/// - `tip`: The hash of the tip of the best chain, if any header has been added.
+#[derive(Debug, Clone, Default)]
+pub struct LightClient {
+    pub headers: HashMap<BlockHash, HeaderEntry>,
+    pub tip: Option<BlockHash>,
+}
+
+impl LightClient {
+    /// Creates a light client with no headers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a header.
+    ///
+    /// The header's parent must already be known, or the header must be the first one
+    /// without a parent (the genesis block). The tip moves to the new header if its
+    /// total work is strictly higher than the current tip's.
+    ///
+    /// ## Returns
+    /// - `true` if the header was added.
+    /// - `false` if it is a duplicate, an orphan, or a second genesis header.
+    pub fn add_header(&mut self, header: BlockHeader) -> bool {
+        if self.headers.contains_key(&header.hash) {
+            return false;
+        }
+
+        let Some(choice) = fork_choice(&self.headers, self.tip.as_ref(), header.parent.as_ref(), header.score) else {
+            return false;
+        };
+        let (height, total_work) = (choice.height, choice.total_work);
+        self.headers.insert(header.hash, HeaderEntry { header, height, total_work });
+        if choice.is_best {
+            self.tip = Some(header.hash);
+        }
+        true
+    }
+
+    /// Returns the tip of the best chain, if any header has been added.
+    pub fn tip(&self) -> Option<&HeaderEntry> {
+        self.tip.as_ref().and_then(|hash| self.headers.get(hash))
+    }
+
+    /// Returns the header with the given block hash, if it is known.
+    pub fn get(&self, hash: &BlockHash) -> Option<&HeaderEntry> {
+        self.headers.get(hash)
+    }
+
+    /// Returns the hashes of the best chain, from genesis to the tip.
+    pub fn best_chain(&self) -> Vec<BlockHash> {
+        let mut chain = Vec::new();
+        let mut current = self.tip();
+        while let Some(entry) = current {
+            chain.push(entry.header.hash);
+            current = entry.header.parent.as_ref().and_then(|parent| self.headers.get(parent));
+        }
+        chain.reverse();
+        chain
+    }
+
+    /// Returns the number of confirmations of a block on the best chain.
+    ///
+    /// ## Returns
+    /// `Some(1)` for the tip, one more for each block below it, or `None` if the block
+    /// is not on the best chain.
+    pub fn confirmations(&self, hash: &BlockHash) -> Option<u32> {
+        let entry = self.headers.get(hash)?;
+        let tip = self.tip()?;
+        let mut current = Some(tip);
+        while let Some(ancestor) = current {
+            if ancestor.height < entry.height {
+                return None;
+            }
+            if ancestor.header.hash == *hash {
+                return Some(tip.height - entry.height + 1);
+            }
+            current = ancestor.header.parent.as_ref().and_then(|parent| self.headers.get(parent));
+        }
+        None
+    }
+
+    /// Verifies that a transaction is included in a block on the best chain.
+    ///
+    /// ## Returns
+    /// The number of confirmations of the containing block, or `None` if the proof is
+    /// invalid or the block is not on the best chain.
+    pub fn verify_transaction(&self, proof: &TransactionProof) -> Option<u32> {
+        let entry = self.headers.get(&proof.block)?;
+        if !proof.verify(&entry.header) {
+            return None;
+        }
+        self.confirmations(&proof.block)
+    }
+
+    /// Verifies that an output is unspent in the state after a block on the best chain.
+    ///
+    /// ## Returns
+    /// The number of confirmations of that block, or `None` if the proof is invalid or
+    /// the block is not on the best chain.
+    pub fn verify_utxo(&self, proof: &UtxoProof, txo: &TXO) -> Option<u32> {
+        let entry = self.headers.get(&proof.block)?;
+        if !proof.verify_unspent(&entry.header, txo) {
+            return None;
+        }
+        self.confirmations(&proof.block)
+    }
+}
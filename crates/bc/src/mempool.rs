@@ -0,0 +1,232 @@
+//! # Mempool
+//!
+//! This module implements a pool of unconfirmed transactions waiting to be mined.
+//!
+//! Transactions are admitted only if they are valid against the current chain context and
+//! pass the pool's [`FeePolicy`]. Block templates are filled in order of fee per logical
+//! action, up to a block size limit.
+//!
+//! Two pool transactions conflict when they spend the same transparent output or note.
+//! The pool's [`ReplacementPolicy`] decides whether a conflicting transaction is rejected
+//! or replaces the transactions it conflicts with; replaced transactions are reported
+//! and recorded as [`Eviction`]s.
+
+use std::fmt;
+use rayon::prelude::*;
+use crate::amount::{Amount, MAX_MONEY};
+use crate::block::BCBlock;
+use crate::context::BCContext;
+use crate::fees::{self, FeePolicy};
+use crate::transaction::{BCTransaction, TxId};
+use crate::validation::ValidationError;
+
+/// Policy deciding which of two conflicting transactions stays in the pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReplacementPolicy {
+    /// The transaction seen first is kept and conflicting transactions are rejected.
+    #[default]
+    FirstSeen,
+    /// A conflicting transaction replaces the transactions it conflicts with if its fee
+    /// exceeds their combined fee by at least `min_increment`.
+    FeeBump { min_increment: Amount },
+}
+
+/// Reason a transaction was not admitted to the pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MempoolError {
+    /// The transaction is already in the pool.
+    Duplicate,
+    /// The transaction is rejected by the fee policy.
+    FeePolicy,
+    /// The transaction is invalid against the current context.
+    Invalid(ValidationError),
+    /// The transaction conflicts with a pool transaction and replacement is disabled.
+    Conflict,
+    /// The transaction conflicts with pool transactions but does not pay enough to replace them.
+    InsufficientFeeBump { required: Amount },
+}
+
+impl fmt::Display for MempoolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MempoolError::Duplicate => write!(f, "Transaction already in the pool"),
+            MempoolError::FeePolicy => write!(f, "Rejected by the fee policy"),
+            MempoolError::Invalid(err) => write!(f, "Invalid transaction: {}", err),
+            MempoolError::Conflict => write!(f, "Conflicts with a pool transaction"),
+            MempoolError::InsufficientFeeBump { required } => {
+                write!(f, "Replacement fee must be at least {}", required)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MempoolError {}
+
+/// A pool transaction evicted by a conflicting replacement.
+///
+/// ## Fields
+/// - `evicted`: The transaction removed from the pool.
+/// - `replacement`: The id of the transaction that replaced it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Eviction {
+    pub evicted: BCTransaction,
+    pub replacement: TxId,
+}
+
+/// A pool of unconfirmed transactions.
+///
+/// ## Fields
+/// - `policy`: The fee policy used to admit transactions.
+/// - `replacement`: The policy used to resolve conflicts between transactions.
+/// - `transactions`: The admitted transactions, in arrival order.
+/// - `evictions`: Every transaction evicted by a replacement, in eviction order.
+#[derive(Debug, Clone, Default)]
+pub struct Mempool {
+    pub policy: FeePolicy,
+    pub replacement: ReplacementPolicy,
+    pub transactions: Vec<BCTransaction>,
+    pub evictions: Vec<Eviction>,
+}
+
+impl Mempool {
+    /// Creates an empty first-seen mempool with the given fee policy.
+    pub fn new(policy: FeePolicy) -> Self {
+        Self::with_replacement(policy, ReplacementPolicy::FirstSeen)
+    }
+
+    /// Creates an empty mempool with the given fee and replacement policies.
+    pub fn with_replacement(policy: FeePolicy, replacement: ReplacementPolicy) -> Self {
+        Self {
+            policy,
+            replacement,
+            transactions: Vec::new(),
+            evictions: Vec::new(),
+        }
+    }
+
+    /// Adds a transaction to the mempool.
+    ///
+    /// ## Parameters
+    /// - `tx`: The transaction to add.
+    /// - `context`: The context of the current chain tip.
+    ///
+    /// ## Returns
+    /// - `true` if the transaction was admitted.
+    /// - `false` if it is already in the pool, invalid, rejected by the fee policy, or
+    ///   loses a conflict under the replacement policy.
+    pub fn add_transaction(&mut self, tx: BCTransaction, context: &BCContext) -> bool {
+        self.try_add_transaction(tx, context).is_ok()
+    }
+
+    /// Adds a transaction to the mempool, replacing conflicting transactions if the
+    /// replacement policy allows it.
+    ///
+    /// ## Returns
+    /// The pool transactions evicted by the new transaction, or the reason it was not admitted.
+    pub fn try_add_transaction(&mut self, tx: BCTransaction, context: &BCContext) -> Result<Vec<BCTransaction>, MempoolError> {
+        if self.contains(&tx) {
+            return Err(MempoolError::Duplicate);
+        }
+        if !self.policy.admits(&tx) {
+            return Err(MempoolError::FeePolicy);
+        }
+        tx.validate(context).map_err(MempoolError::Invalid)?;
+
+        let conflicts = self.conflicts(&tx);
+        if !conflicts.is_empty() {
+            match self.replacement {
+                ReplacementPolicy::FirstSeen => return Err(MempoolError::Conflict),
+                ReplacementPolicy::FeeBump { min_increment } => {
+                    let replaced: Option<Amount> = conflicts.iter().map(|index| self.transactions[*index].fee).sum();
+                    // A required fee above MAX_MONEY can never be paid.
+                    let required = replaced.and_then(|fee| fee + min_increment);
+                    if required.is_none_or(|required| tx.fee < required) {
+                        let required = required.unwrap_or(Amount::const_from_i64(MAX_MONEY));
+                        return Err(MempoolError::InsufficientFeeBump { required });
+                    }
+                }
+            }
+        }
+
+        let replacement = tx.txid();
+        let mut evicted = Vec::new();
+        for index in conflicts.into_iter().rev() {
+            let old = self.transactions.remove(index);
+            self.evictions.push(Eviction { evicted: old.clone(), replacement });
+            evicted.push(old);
+        }
+        evicted.reverse();
+        self.transactions.push(tx);
+        Ok(evicted)
+    }
+
+    /// Returns the positions of the pool transactions spending any input of a transaction.
+    pub fn conflicts(&self, tx: &BCTransaction) -> Vec<usize> {
+        self.transactions
+            .iter()
+            .enumerate()
+            .filter(|(_, other)| {
+                other.transparent_inputs.iter().any(|txo| tx.transparent_inputs.contains(txo))
+                    || other.shielded_inputs.iter().any(|note| tx.shielded_inputs.contains(note))
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Checks if the mempool contains a transaction.
+    pub fn contains(&self, tx: &BCTransaction) -> bool {
+        self.transactions.contains(tx)
+    }
+
+    /// Returns the number of transactions in the mempool.
+    pub fn len(&self) -> usize {
+        self.transactions.len()
+    }
+
+    /// Checks if the mempool is empty.
+    pub fn is_empty(&self) -> bool {
+        self.transactions.is_empty()
+    }
+
+    /// Removes the transactions mined in a block.
+    pub fn remove_block(&mut self, block: &BCBlock) {
+        self.transactions.retain(|tx| !block.transactions.contains(tx));
+    }
+
+    /// Removes transactions that are no longer valid against the given context.
+    ///
+    /// ## Returns
+    /// The number of transactions removed.
+    pub fn revalidate(&mut self, context: &BCContext) -> usize {
+        // Every transaction is checked against the same context, so all checks can run in parallel.
+        let valid: Vec<bool> = self.transactions.par_iter().map(|tx| tx.is_valid(context)).collect();
+        let before = self.transactions.len();
+        let mut valid = valid.into_iter();
+        self.transactions.retain(|_| valid.next().unwrap_or(false));
+        before - self.transactions.len()
+    }
+
+    /// Selects transactions for a block template.
+    ///
+    /// Transactions are taken in decreasing order of fee per logical action (ties keep
+    /// arrival order) while they fit in `max_size` bytes.
+    pub fn block_template(&self, max_size: usize) -> Vec<BCTransaction> {
+        let mut candidates: Vec<&BCTransaction> = self.transactions.iter().collect();
+        // Compare fee rates by cross-multiplying to avoid rounding.
+        candidates.sort_by(|a, b| {
+            let rate_a = a.fee.zatoshi() as i128 * fees::logical_actions(b).max(1) as i128;
+            let rate_b = b.fee.zatoshi() as i128 * fees::logical_actions(a).max(1) as i128;
+            rate_b.cmp(&rate_a)
+        });
+
+        let mut size = 0;
+        let mut selected = Vec::new();
+        for tx in candidates {
+            if size + tx.size() <= max_size {
+                size += tx.size();
+                selected.push(tx.clone());
+            }
+        }
+        selected
+    }
+}
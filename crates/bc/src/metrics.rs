@@ -0,0 +1,243 @@
+//! # Chain Metrics
+//!
+//! This module computes standard best-chain analysis over the blocks produced in a run:
+//! chain growth rate, chain quality, orphan (stale) rate and the distribution of reorg
+//! depths.
+//!
+//! Metrics are computed from the blocks' parent links alone, by replaying the blocks in
+//! arrival order and following the chain chosen by [`fork_choice`], as in
+//! [`BlockTree`](crate::chain::BlockTree).
+//! Results can be exported as CSV.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+use crate::block::{BCBlock, BlockHash};
+use crate::chain::{fork_choice, ChainWork};
+use crate::transaction::BCTransaction;
+
+/// A block observed during a run.
+///
+/// ## Fields
+/// - `block`: The block itself.
+/// - `time`: The time at which the block was produced.
+/// - `honest`: Whether the block was produced by an honest party.
+#[derive(Debug, Clone)]
+pub struct ObservedBlock<T = BCTransaction> {
+    pub block: BCBlock<T>,
+    pub time: f64,
+    pub honest: bool,
+}
+
+/// Chain quality over a window of consecutive best-chain blocks.
+///
+/// ## Fields
+/// - `start_height`: The height of the first block in the window.
+/// - `honest_blocks`: The number of honest blocks in the window.
+/// - `quality`: The fraction of honest blocks in the window.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QualityWindow {
+    pub start_height: u32,
+    pub honest_blocks: usize,
+    pub quality: f64,
+}
+
+/// Best-chain metrics of a run.
+///
+/// ## Fields
+/// - `total_blocks`: The number of blocks connected to the genesis block.
+/// - `best_chain`: The hashes of the best chain, from genesis to the tip.
+/// - `duration`: The time between the first and the last block.
+/// - `growth_rate`: The number of best-chain blocks after genesis per unit of time.
+/// - `orphan_rate`: The fraction of blocks that are not on the best chain.
+/// - `chain_quality`: The fraction of honest blocks on the best chain.
+/// - `window`: The number of blocks in each chain quality window.
+/// - `quality_windows`: The chain quality of every window of `window` consecutive best-chain blocks.
+/// - `reorg_depths`: The number of reorgs of each depth, where the depth is the number of
+///   blocks removed from the best chain.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChainMetrics {
+    pub total_blocks: usize,
+    pub best_chain: Vec<BlockHash>,
+    pub duration: f64,
+    pub growth_rate: f64,
+    pub orphan_rate: f64,
+    pub chain_quality: f64,
+    pub window: usize,
+    pub quality_windows: Vec<QualityWindow>,
+    pub reorg_depths: BTreeMap<u32, usize>,
+}
+
+/// Position of a replayed block in the tree.
+struct Node {
+    parent: Option<BlockHash>,
+    height: u32,
+    total_score: i64,
+    honest: bool,
+}
+
+impl ChainWork for Node {
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn total_work(&self) -> i64 {
+        self.total_score
+    }
+}
+
+impl ChainMetrics {
+    /// Computes the metrics of a run.
+    ///
+    /// Blocks whose parent has not been seen before them, and parentless blocks other
+    /// than the first, are ignored.
+    ///
+    /// ## Parameters
+    /// - `blocks`: The blocks produced during the run, in arrival order.
+    /// - `window`: The number of blocks in each chain quality window.
+    pub fn compute<T>(blocks: &[ObservedBlock<T>], window: usize) -> Self {
+        let mut nodes: HashMap<BlockHash, Node> = HashMap::new();
+        let mut tip: Option<BlockHash> = None;
+        let mut reorg_depths = BTreeMap::new();
+        let (mut first_time, mut last_time) = (f64::INFINITY, f64::NEG_INFINITY);
+
+        for observed in blocks {
+            let block = &observed.block;
+            if nodes.contains_key(&block.hash) {
+                continue;
+            }
+            let Some(choice) = fork_choice(&nodes, tip.as_ref(), block.parent.as_ref(), block.score) else {
+                continue;
+            };
+            first_time = first_time.min(observed.time);
+            last_time = last_time.max(observed.time);
+
+            let (height, total_score) = (choice.height, choice.total_work);
+            let node = Node { parent: block.parent, height, total_score, honest: observed.honest };
+            nodes.insert(block.hash, node);
+            if !choice.is_best {
+                continue;
+            }
+
+            if let Some(old_tip) = tip.filter(|old_tip| block.parent != Some(*old_tip)) {
+                let depth = nodes[&old_tip].height - fork_height(&nodes, old_tip, block.hash);
+                *reorg_depths.entry(depth).or_insert(0) += 1;
+            }
+            tip = Some(block.hash);
+        }
+
+        let mut best_chain = Vec::new();
+        let mut current = tip;
+        while let Some(hash) = current {
+            best_chain.push(hash);
+            current = nodes[&hash].parent;
+        }
+        best_chain.reverse();
+
+        let honest: Vec<bool> = best_chain.iter().map(|hash| nodes[hash].honest).collect();
+        let quality_windows = if window == 0 {
+            Vec::new()
+        } else {
+            honest
+                .windows(window)
+                .enumerate()
+                .map(|(start, blocks)| {
+                    let honest_blocks = blocks.iter().filter(|honest| **honest).count();
+                    QualityWindow {
+                        start_height: start as u32,
+                        honest_blocks,
+                        quality: honest_blocks as f64 / window as f64,
+                    }
+                })
+                .collect()
+        };
+
+        let total_blocks = nodes.len();
+        let duration = if total_blocks == 0 { 0.0 } else { last_time - first_time };
+        let growth_rate = if duration > 0.0 { (best_chain.len() - 1) as f64 / duration } else { 0.0 };
+        let ratio = |count: usize, total: usize| if total == 0 { 0.0 } else { count as f64 / total as f64 };
+
+        Self {
+            total_blocks,
+            duration,
+            growth_rate,
+            orphan_rate: ratio(total_blocks - best_chain.len(), total_blocks),
+            chain_quality: ratio(honest.iter().filter(|honest| **honest).count(), honest.len()),
+            best_chain,
+            window,
+            quality_windows,
+            reorg_depths,
+        }
+    }
+
+    /// Returns the lowest chain quality over all windows, if the chain is long enough
+    /// to contain one.
+    pub fn min_quality(&self) -> Option<f64> {
+        self.quality_windows.iter().map(|window| window.quality).reduce(f64::min)
+    }
+
+    /// Returns the summary metrics as CSV with a `metric,value` header.
+    pub fn summary_csv(&self) -> String {
+        let mut csv = String::from("metric,value\n");
+        let rows = [
+            ("total_blocks", self.total_blocks as f64),
+            ("best_chain_length", self.best_chain.len() as f64),
+            ("duration", self.duration),
+            ("growth_rate", self.growth_rate),
+            ("orphan_rate", self.orphan_rate),
+            ("chain_quality", self.chain_quality),
+            ("min_window_quality", self.min_quality().unwrap_or(f64::NAN)),
+            ("reorgs", self.reorg_depths.values().sum::<usize>() as f64),
+        ];
+        for (metric, value) in rows {
+            writeln!(csv, "{},{}", metric, value).unwrap();
+        }
+        csv
+    }
+
+    /// Returns the chain quality windows as CSV with a
+    /// `start_height,window,honest_blocks,quality` header.
+    pub fn quality_csv(&self) -> String {
+        let mut csv = String::from("start_height,window,honest_blocks,quality\n");
+        for window in &self.quality_windows {
+            writeln!(csv, "{},{},{},{}", window.start_height, self.window, window.honest_blocks, window.quality).unwrap();
+        }
+        csv
+    }
+
+    /// Returns the reorg depth distribution as CSV with a `depth,count` header.
+    pub fn reorg_csv(&self) -> String {
+        let mut csv = String::from("depth,count\n");
+        for (depth, count) in &self.reorg_depths {
+            writeln!(csv, "{},{}", depth, count).unwrap();
+        }
+        csv
+    }
+
+    /// Writes `summary.csv`, `chain_quality.csv` and `reorgs.csv` to a directory,
+    /// creating it if needed.
+    pub fn write_csv(&self, dir: &Path) -> io::Result<()> {
+        fs::create_dir_all(dir)?;
+        fs::write(dir.join("summary.csv"), self.summary_csv())?;
+        fs::write(dir.join("chain_quality.csv"), self.quality_csv())?;
+        fs::write(dir.join("reorgs.csv"), self.reorg_csv())
+    }
+}
+
+/// Returns the height of the last common ancestor of two blocks.
+fn fork_height(nodes: &HashMap<BlockHash, Node>, a: BlockHash, b: BlockHash) -> u32 {
+    let (mut a, mut b) = (a, b);
+    while nodes[&a].height > nodes[&b].height {
+        a = nodes[&a].parent.unwrap();
+    }
+    while nodes[&b].height > nodes[&a].height {
+        b = nodes[&b].parent.unwrap();
+    }
+    while a != b {
+        a = nodes[&a].parent.unwrap();
+        b = nodes[&b].parent.unwrap();
+    }
+    nodes[&a].height
+}
@@ -0,0 +1,239 @@
+//! # Consensus Parameters
+//!
+//! This module defines the network upgrades of the Best-Chain protocol and the heights at
+//! which they activate.
+//!
+//! Consensus rules are versioned: transaction and block validation look up the
+//! [`ConsensusRules`] in effect at the height being validated, so a simulation can switch
+//! rules (including the Trailing Finality Layer) on part-way through a chain.
+
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use crate::amount::Amount;
+
+/// Number of blocks between subsidy halvings before Blossom.
+pub const PRE_BLOSSOM_HALVING_INTERVAL: u32 = 840_000;
+
+/// Number of blocks over which the mainnet block subsidy ramps up from zero.
+pub const SUBSIDY_SLOW_START_INTERVAL: u32 = 20_000;
+
+/// Block subsidy before the first halving and before Blossom (12.5 ZEC).
+pub const MAX_BLOCK_SUBSIDY: Amount = Amount::const_from_i64(1_250_000_000);
+
+/// Maximum serialized size of a block in bytes.
+pub const MAX_BLOCK_SIZE: usize = 2_000_000;
+
+/// Ratio between the pre- and post-Blossom target block spacings.
+pub const BLOSSOM_POW_TARGET_SPACING_RATIO: u32 = 2;
+
+/// A named network upgrade.
+///
+/// Upgrades are ordered by their position in the upgrade sequence.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Eq, Hash, PartialEq, Ord, PartialOrd)]
+pub enum NetworkUpgrade {
+    /// Introduces transaction expiry.
+    Overwinter,
+    /// Introduces shielded transactions.
+    Sapling,
+    /// Halves the block target spacing (and the block subsidy with it).
+    Blossom,
+    /// Allows coinbase transactions to create shielded outputs.
+    Heartwood,
+    /// Canopy network upgrade.
+    Canopy,
+    /// NU5 network upgrade.
+    Nu5,
+    /// NU6 network upgrade.
+    Nu6,
+    /// Activates the Trailing Finality Layer.
+    Tfl,
+}
+
+impl NetworkUpgrade {
+    /// All network upgrades, in activation order.
+    pub const ALL: [NetworkUpgrade; 8] = [
+        NetworkUpgrade::Overwinter,
+        NetworkUpgrade::Sapling,
+        NetworkUpgrade::Blossom,
+        NetworkUpgrade::Heartwood,
+        NetworkUpgrade::Canopy,
+        NetworkUpgrade::Nu5,
+        NetworkUpgrade::Nu6,
+        NetworkUpgrade::Tfl,
+    ];
+}
+
+/// The set of consensus rules in effect at a given height.
+///
+/// ## Fields
+/// - `upgrade`: The most recent network upgrade active at this height, if any.
+/// - `expiry_enforced`: Whether transaction expiry heights are enforced.
+/// - `shielded_enabled`: Whether transactions may have shielded inputs or outputs.
+/// - `shielded_coinbase`: Whether coinbase transactions may have shielded outputs.
+/// - `coinbase_maturity`: Number of blocks a coinbase output must wait before it can be spent.
+/// - `block_subsidy`: Maximum issuance allowed in a block at this height.
+/// - `max_block_size`: Maximum serialized size of a block in bytes.
+/// - `tfl_active`: Whether the Trailing Finality Layer is active.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConsensusRules {
+    pub upgrade: Option<NetworkUpgrade>,
+    pub expiry_enforced: bool,
+    pub shielded_enabled: bool,
+    pub shielded_coinbase: bool,
+    pub coinbase_maturity: u32,
+    pub block_subsidy: Amount,
+    pub max_block_size: usize,
+    pub tfl_active: bool,
+}
+
+/// Consensus parameters for a best-chain network.
+///
+/// ## Fields
+/// - `activation_heights`: The height at which each scheduled upgrade activates.
+///   Upgrades that are absent never activate.
+/// - `coinbase_maturity`: Number of blocks a coinbase output must wait before it can be spent.
+/// - `subsidy_slow_start_interval`: Number of blocks over which the block subsidy ramps up
+///   from zero. Halvings are shifted by half of it.
+/// - `max_block_size`: Maximum serialized size of a block in bytes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Eq, PartialEq)]
+pub struct ConsensusParams {
+    pub activation_heights: BTreeMap<NetworkUpgrade, u32>,
+    pub coinbase_maturity: u32,
+    pub subsidy_slow_start_interval: u32,
+    pub max_block_size: usize,
+}
+
+impl Default for ConsensusParams {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConsensusParams {
+    /// Creates parameters for a test network.
+    ///
+    /// Every upgrade except `Tfl` is active from genesis, and coinbase maturity and the
+    /// subsidy slow start are disabled.
+    pub fn new() -> Self {
+        let activation_heights = NetworkUpgrade::ALL
+            .iter()
+            .filter(|upgrade| **upgrade != NetworkUpgrade::Tfl)
+            .map(|upgrade| (*upgrade, 0))
+            .collect();
+        Self {
+            activation_heights,
+            coinbase_maturity: 0,
+            subsidy_slow_start_interval: 0,
+            max_block_size: MAX_BLOCK_SIZE,
+        }
+    }
+
+    /// Creates parameters matching Zcash mainnet.
+    ///
+    /// The Trailing Finality Layer is not scheduled.
+    pub fn mainnet() -> Self {
+        let activation_heights = [
+            (NetworkUpgrade::Overwinter, 347_500),
+            (NetworkUpgrade::Sapling, 419_200),
+            (NetworkUpgrade::Blossom, 653_600),
+            (NetworkUpgrade::Heartwood, 903_000),
+            (NetworkUpgrade::Canopy, 1_046_400),
+            (NetworkUpgrade::Nu5, 1_687_104),
+            (NetworkUpgrade::Nu6, 2_726_400),
+        ]
+        .into_iter()
+        .collect();
+        Self {
+            activation_heights,
+            coinbase_maturity: super::transaction::COINBASE_MATURITY,
+            subsidy_slow_start_interval: SUBSIDY_SLOW_START_INTERVAL,
+            max_block_size: MAX_BLOCK_SIZE,
+        }
+    }
+
+    /// Sets (or, with `None`, removes) the activation height of an upgrade.
+    pub fn set_activation_height(&mut self, upgrade: NetworkUpgrade, height: Option<u32>) {
+        match height {
+            Some(height) => self.activation_heights.insert(upgrade, height),
+            None => self.activation_heights.remove(&upgrade),
+        };
+    }
+
+    /// Returns the activation height of an upgrade, or `None` if it is not scheduled.
+    pub fn activation_height(&self, upgrade: NetworkUpgrade) -> Option<u32> {
+        self.activation_heights.get(&upgrade).copied()
+    }
+
+    /// Checks if an upgrade is active at the given height.
+    pub fn is_active(&self, upgrade: NetworkUpgrade, height: u32) -> bool {
+        self.activation_height(upgrade).is_some_and(|activation| height >= activation)
+    }
+
+    /// Returns the most recent upgrade active at the given height, if any.
+    pub fn current_upgrade(&self, height: u32) -> Option<NetworkUpgrade> {
+        NetworkUpgrade::ALL
+            .iter()
+            .rev()
+            .find(|upgrade| self.is_active(**upgrade, height))
+            .copied()
+    }
+
+    /// Returns the number of blocks by which halvings are shifted to make up for the slow start.
+    pub fn subsidy_slow_start_shift(&self) -> u32 {
+        self.subsidy_slow_start_interval / 2
+    }
+
+    /// Returns the number of subsidy halvings that have happened by the given height.
+    ///
+    /// The subsidy halves every `PRE_BLOSSOM_HALVING_INTERVAL` blocks after the slow-start
+    /// shift; after Blossom the halving interval is scaled to the shorter block spacing.
+    /// Must not be called for heights within the slow-start shift.
+    pub fn halving(&self, height: u32) -> u32 {
+        let ratio = BLOSSOM_POW_TARGET_SPACING_RATIO;
+        let shift = self.subsidy_slow_start_shift();
+        match self.activation_height(NetworkUpgrade::Blossom) {
+            Some(blossom) if height >= blossom => {
+                let scaled = blossom.saturating_sub(shift) * ratio + (height - blossom);
+                scaled / (PRE_BLOSSOM_HALVING_INTERVAL * ratio)
+            }
+            _ => (height - shift) / PRE_BLOSSOM_HALVING_INTERVAL,
+        }
+    }
+
+    /// Returns the block subsidy at the given height.
+    ///
+    /// During the slow start the subsidy ramps up linearly from zero. After it, the subsidy
+    /// halves as given by `halving`, and after Blossom it is scaled to the shorter block spacing.
+    pub fn block_subsidy(&self, height: u32) -> Amount {
+        let max_subsidy = MAX_BLOCK_SUBSIDY.zatoshi();
+        let slow_start = self.subsidy_slow_start_interval;
+        if height < slow_start / 2 {
+            return Amount::const_from_i64(max_subsidy / slow_start as i64 * height as i64);
+        }
+        if height < slow_start {
+            return Amount::const_from_i64(max_subsidy / slow_start as i64 * (height as i64 + 1));
+        }
+        let subsidy = if self.is_active(NetworkUpgrade::Blossom, height) {
+            max_subsidy / BLOSSOM_POW_TARGET_SPACING_RATIO as i64
+        } else {
+            max_subsidy
+        };
+        Amount::const_from_i64(subsidy.checked_shr(self.halving(height)).unwrap_or(0))
+    }
+
+    /// Returns the consensus rules in effect at the given height.
+    pub fn rules_at(&self, height: u32) -> ConsensusRules {
+        ConsensusRules {
+            upgrade: self.current_upgrade(height),
+            expiry_enforced: self.is_active(NetworkUpgrade::Overwinter, height),
+            shielded_enabled: self.is_active(NetworkUpgrade::Sapling, height),
+            shielded_coinbase: self.is_active(NetworkUpgrade::Heartwood, height),
+            coinbase_maturity: self.coinbase_maturity,
+            block_subsidy: self.block_subsidy(height),
+            max_block_size: self.max_block_size,
+            tfl_active: self.is_active(NetworkUpgrade::Tfl, height),
+        }
+    }
+}
@@ -0,0 +1,304 @@
+//! # Proof-of-Stake Longest Chain
+//!
+//! This module implements a slot-based proof-of-stake longest-chain protocol in the
+//! style of Ouroboros Praos, as an alternative to proof-of-work best-chain.
+//!
+//! Time is divided into slots. In each slot every party is independently elected leader
+//! with probability `1 - (1 - f)^α`, where `α` is its relative stake and `f` the active
+//! slot coefficient, using randomness derived from a seeded beacon in place of a VRF.
+//! Slots may therefore be empty or have several leaders. Leaders extend their current
+//! chain, and nodes adopt a strictly longer valid chain unless switching to it would
+//! roll back more than `security_param` blocks.
+//!
+//! Ledger state reuses `BCBlock` and `BCContext`: every node keeps a [`BlockTree`] of the
+//! blocks it has seen, and each block pays the block subsidy to its leader.
+
+use std::collections::HashMap;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use crate::block::{BCBlock, BlockHash};
+use crate::chain::BlockTree;
+use crate::commitment;
+use crate::context::BCContext;
+use crate::metrics::ObservedBlock;
+use crate::transaction::{Address, BCTransaction, TXO};
+
+/// Parameters of the protocol.
+///
+/// ## Fields
+/// - `active_slot_coeff`: The probability `f` that a party holding all the stake leads a slot.
+/// - `security_param`: The maximum number of blocks a node rolls back when switching chains (`k`).
+/// - `beacon`: The seed of the leader election randomness.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PraosParams {
+    pub active_slot_coeff: f64,
+    pub security_param: u32,
+    pub beacon: u64,
+}
+
+/// Stake-weighted slot leader election.
+///
+/// ## Fields
+/// - `params`: The protocol parameters.
+/// - `stakes`: The stake of each party, indexed by party.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LeaderElection {
+    pub params: PraosParams,
+    pub stakes: Vec<u64>,
+}
+
+impl LeaderElection {
+    /// Creates a leader election over the given stake distribution.
+    pub fn new(params: PraosParams, stakes: Vec<u64>) -> Self {
+        Self { params, stakes }
+    }
+
+    /// Returns the relative stake of a party.
+    pub fn relative_stake(&self, party: usize) -> f64 {
+        let total: u64 = self.stakes.iter().sum();
+        if total == 0 {
+            return 0.0;
+        }
+        self.stakes.get(party).map_or(0.0, |stake| *stake as f64 / total as f64)
+    }
+
+    /// Returns the probability that a party leads any given slot.
+    pub fn leader_probability(&self, party: usize) -> f64 {
+        1.0 - (1.0 - self.params.active_slot_coeff).powf(self.relative_stake(party))
+    }
+
+    /// Returns the beacon output of a party for a slot, uniform in `[0, 1)`.
+    fn beacon_value(&self, slot: u64, party: usize) -> f64 {
+        let digest = commitment::digest(&(self.params.beacon, slot, party as u64));
+        let value = u64::from_le_bytes(digest[..8].try_into().unwrap());
+        (value >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Checks if a party is a leader of a slot.
+    pub fn is_leader(&self, slot: u64, party: usize) -> bool {
+        self.beacon_value(slot, party) < self.leader_probability(party)
+    }
+
+    /// Returns the leaders of a slot, which may be none or several.
+    pub fn leaders(&self, slot: u64) -> Vec<usize> {
+        (0..self.stakes.len()).filter(|party| self.is_leader(slot, *party)).collect()
+    }
+}
+
+/// A block together with the slot it was produced in and its leader.
+///
+/// ## Fields
+/// - `block`: The block itself.
+/// - `slot`: The slot the block was produced in.
+/// - `leader`: The party that produced the block.
+#[derive(Debug, Clone)]
+pub struct PraosBlock {
+    pub block: BCBlock,
+    pub slot: u64,
+    pub leader: usize,
+}
+
+/// A node following the longest chain with bounded rollback.
+///
+/// ## Fields
+/// - `id`: The party this node belongs to.
+/// - `tree`: Every valid block the node has received.
+/// - `slots`: The slot of each block in the tree.
+/// - `tip`: The tip of the node's selected chain.
+/// - `rejected_forks`: The number of longer chains not adopted because they forked too deep.
+#[derive(Debug, Clone)]
+pub struct PraosNode {
+    pub id: usize,
+    pub tree: BlockTree<BCContext>,
+    pub slots: HashMap<BlockHash, u64>,
+    pub tip: BlockHash,
+    pub rejected_forks: usize,
+}
+
+impl PraosNode {
+    /// Creates a node whose chain consists of the genesis block.
+    ///
+    /// ## Parameters
+    /// - `id`: The party this node belongs to.
+    /// - `genesis`: The genesis block, produced in slot `0`.
+    /// - `context`: The context the genesis block is applied to.
+    pub fn new(id: usize, genesis: &BCBlock, context: BCContext) -> Self {
+        let mut tree = BlockTree::new(context);
+        assert!(tree.add_block(genesis.clone()), "invalid genesis block");
+        Self {
+            id,
+            tree,
+            slots: HashMap::from([(genesis.hash, 0)]),
+            tip: genesis.hash,
+            rejected_forks: 0,
+        }
+    }
+
+    /// Returns the height of the node's selected chain.
+    pub fn height(&self) -> u32 {
+        self.tree.blocks[&self.tip].height
+    }
+
+    /// Returns the hashes of the node's selected chain, from genesis to the tip.
+    pub fn chain(&self) -> Vec<BlockHash> {
+        let mut chain = self.tree.ancestors(&self.tip);
+        chain.reverse();
+        chain
+    }
+
+    /// Returns the context at the tip of the node's selected chain.
+    pub fn tip_context(&self) -> &BCContext {
+        &self.tree.blocks[&self.tip].context
+    }
+
+    /// Builds a block extending the node's selected chain that pays the block subsidy to the node.
+    pub fn propose(&self, slot: u64, hash: BlockHash) -> PraosBlock {
+        let context = self.tip_context();
+        let subsidy = context.rules().block_subsidy;
+        let coinbase = BCTransaction {
+            transparent_outputs: vec![TXO {
+                tx: BCTransaction::default(),
+                index: slot as usize,
+                value: subsidy,
+                address: Address(self.id as u32),
+            }],
+            issuance: subsidy,
+            ..BCTransaction::default()
+        };
+        let block = BCBlock {
+            parent: Some(self.tip),
+            score: 1,
+            transactions: vec![coinbase],
+            hash,
+            state_root: None,
+        };
+        PraosBlock { block, slot, leader: self.id }
+    }
+
+    /// Receives a block and selects the longest chain.
+    ///
+    /// The block must extend a known block from an earlier slot, be produced by a
+    /// leader of its slot and be valid against its parent's context. The node switches
+    /// to the block's chain if it is strictly longer and forks from the selected chain
+    /// at most `security_param` blocks below the tip.
+    ///
+    /// ## Returns
+    /// - `true` if the block was added to the node's tree.
+    /// - `false` if it is a duplicate, an orphan or invalid.
+    pub fn receive(&mut self, block: &PraosBlock, election: &LeaderElection) -> bool {
+        let parent_slot = match block.block.parent.and_then(|parent| self.slots.get(&parent)) {
+            Some(slot) => *slot,
+            None => return false,
+        };
+        if block.slot <= parent_slot || !election.is_leader(block.slot, block.leader) {
+            return false;
+        }
+        if !self.tree.add_block(block.block.clone()) {
+            return false;
+        }
+        self.slots.insert(block.block.hash, block.slot);
+
+        let candidate = block.block.hash;
+        if self.tree.blocks[&candidate].height > self.height() {
+            let (rollback, _) = self.tree.reorg_path(Some(&self.tip), &candidate);
+            if rollback.len() <= election.params.security_param as usize {
+                self.tip = candidate;
+            } else {
+                self.rejected_forks += 1;
+            }
+        }
+        true
+    }
+}
+
+/// A run of the protocol with one node per party and a fixed network delay.
+///
+/// ## Fields
+/// - `election`: The leader election.
+/// - `nodes`: The node of each party.
+/// - `delay`: The number of slots a block takes to reach the other nodes.
+/// - `slot`: The last slot that was run.
+/// - `produced`: Every block produced, in production order.
+/// - `pending`: Blocks in transit, with the slot they are delivered in and their recipient.
+#[derive(Debug, Clone)]
+pub struct PraosSimulation {
+    pub election: LeaderElection,
+    pub nodes: Vec<PraosNode>,
+    pub delay: u64,
+    pub slot: u64,
+    pub produced: Vec<PraosBlock>,
+    pub pending: Vec<(u64, usize, PraosBlock)>,
+    rng: StdRng,
+}
+
+impl PraosSimulation {
+    /// Creates a run in which every party starts from the same genesis block.
+    ///
+    /// ## Parameters
+    /// - `election`: The leader election; there is one node per party.
+    /// - `delay`: The number of slots a block takes to reach the other nodes.
+    /// - `context`: The context the genesis block is applied to.
+    pub fn new(election: LeaderElection, delay: u64, context: BCContext) -> Self {
+        let mut rng = StdRng::seed_from_u64(election.params.beacon);
+        let genesis = BCBlock {
+            parent: None,
+            score: 1,
+            transactions: vec![],
+            hash: BlockHash::from_rng(&mut rng),
+            state_root: None,
+        };
+        let nodes = (0..election.stakes.len())
+            .map(|id| PraosNode::new(id, &genesis, context.copy()))
+            .collect();
+        let produced = vec![PraosBlock { block: genesis, slot: 0, leader: 0 }];
+        Self { election, nodes, delay, slot: 0, produced, pending: Vec::new(), rng }
+    }
+
+    /// Runs the next slot.
+    ///
+    /// Blocks due in the slot are delivered first; then every leader of the slot
+    /// extends its chain and sends the block to the other nodes.
+    pub fn step(&mut self) {
+        self.slot += 1;
+        let slot = self.slot;
+
+        let (due, pending): (Vec<_>, Vec<_>) = self.pending.drain(..).partition(|(at, _, _)| *at <= slot);
+        self.pending = pending;
+        for (_, to, block) in due {
+            self.nodes[to].receive(&block, &self.election);
+        }
+
+        for leader in self.election.leaders(slot) {
+            let block = self.nodes[leader].propose(slot, BlockHash::from_rng(&mut self.rng));
+            self.nodes[leader].receive(&block, &self.election);
+            for to in (0..self.nodes.len()).filter(|to| *to != leader) {
+                self.pending.push((slot + self.delay, to, block.clone()));
+            }
+            self.produced.push(block);
+        }
+    }
+
+    /// Runs the given number of slots.
+    pub fn run(&mut self, slots: u64) {
+        for _ in 0..slots {
+            self.step();
+        }
+    }
+
+    /// Returns the fraction of slots run so far that had at least one leader.
+    pub fn active_slot_fraction(&self) -> f64 {
+        if self.slot == 0 {
+            return 0.0;
+        }
+        let active = (1..=self.slot).filter(|slot| !self.election.leaders(*slot).is_empty()).count();
+        active as f64 / self.slot as f64
+    }
+
+    /// Returns the produced blocks with their slot as time, for [`ChainMetrics`](crate::metrics::ChainMetrics).
+    pub fn observed_blocks(&self) -> Vec<ObservedBlock> {
+        self.produced
+            .iter()
+            .map(|block| ObservedBlock { block: block.block.clone(), time: block.slot as f64, honest: true })
+            .collect()
+    }
+}
@@ -0,0 +1,290 @@
+//! # Spam Scenarios
+//!
+//! This module generates congestion scenarios like the 2022 Zcash shielded spam, in
+//! which the transaction pool was flooded with low-fee transactions creating many
+//! shielded outputs.
+//!
+//! Each block, a spammer submits transactions that spend one transparent output into
+//! many notes, while honest users submit ordinary transparent payments. Blocks are filled
+//! from the mempool up to the block size limit. The run records block fullness, the
+//! confirmation latency of honest transactions, the growth of `BCContext::notes` and the
+//! time taken to validate each block.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::time::{Duration, Instant};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use crate::amount::Amount;
+use crate::block::{BCBlock, BlockHash, BLOCK_HEADER_SIZE};
+use crate::context::BCContext;
+use crate::fees::FeePolicy;
+use crate::mempool::Mempool;
+use crate::transaction::{Address, BCTransaction, Note, TxId, TXO};
+use crate::validation::{self, ValidationError};
+
+/// Address of the spammer's outputs.
+pub const SPAMMER: Address = Address(0);
+
+/// Address of the honest users' outputs.
+pub const HONEST: Address = Address(1);
+
+/// Parameters of a spam scenario.
+///
+/// ## Fields
+/// - `blocks`: The number of blocks to mine.
+/// - `spam_per_block`: The number of spam transactions submitted before each block.
+/// - `spam_outputs`: The number of shielded outputs of each spam transaction.
+/// - `spam_fee`: The fee paid by each spam transaction.
+/// - `honest_per_block`: The number of honest transactions submitted before each block.
+/// - `policy`: The fee policy of the mempool.
+/// - `seed`: The seed of the generator drawing block hashes, making runs reproducible.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpamConfig {
+    pub blocks: usize,
+    pub spam_per_block: usize,
+    pub spam_outputs: usize,
+    pub spam_fee: Amount,
+    pub honest_per_block: usize,
+    pub policy: FeePolicy,
+    pub seed: u64,
+}
+
+/// Statistics of a single mined block.
+///
+/// ## Fields
+/// - `height`: The height of the block.
+/// - `transactions`: The number of transactions in the block.
+/// - `spam_transactions`: The number of spam transactions in the block.
+/// - `size`: The estimated size of the block in bytes.
+/// - `fullness`: The size of the block relative to the block size limit.
+/// - `notes`: The number of notes in the context after the block.
+/// - `mempool_size`: The number of transactions left in the mempool after the block.
+/// - `validation_time`: The time taken to validate and apply the block.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlockStats {
+    pub height: u32,
+    pub transactions: usize,
+    pub spam_transactions: usize,
+    pub size: usize,
+    pub fullness: f64,
+    pub notes: usize,
+    pub mempool_size: usize,
+    pub validation_time: Duration,
+}
+
+/// Results of a spam scenario.
+///
+/// ## Fields
+/// - `blocks`: The statistics of every mined block.
+/// - `honest_latencies`: The number of blocks each confirmed honest transaction waited
+///   for, where `1` means it was mined in the next block.
+/// - `unconfirmed_honest`: The number of honest transactions submitted but never confirmed.
+/// - `rejected_spam`: The number of spam transactions refused by the mempool.
+/// - `initial_notes`: The number of notes in the context before the first block.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SpamReport {
+    pub blocks: Vec<BlockStats>,
+    pub honest_latencies: Vec<u32>,
+    pub unconfirmed_honest: usize,
+    pub rejected_spam: usize,
+    pub initial_notes: usize,
+}
+
+impl SpamReport {
+    /// Returns the mean block fullness, or `None` if no block was mined.
+    pub fn mean_fullness(&self) -> Option<f64> {
+        mean(self.blocks.iter().map(|block| block.fullness))
+    }
+
+    /// Returns the mean confirmation latency of honest transactions, or `None` if none was confirmed.
+    pub fn mean_honest_latency(&self) -> Option<f64> {
+        mean(self.honest_latencies.iter().map(|latency| *latency as f64))
+    }
+
+    /// Returns the number of notes added per block, or `None` if no block was mined.
+    pub fn notes_growth(&self) -> Option<f64> {
+        let last = self.blocks.last()?;
+        Some((last.notes - self.initial_notes) as f64 / self.blocks.len() as f64)
+    }
+
+    /// Returns the per-block statistics as CSV with a
+    /// `height,transactions,spam_transactions,size,fullness,notes,mempool_size,validation_us` header.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("height,transactions,spam_transactions,size,fullness,notes,mempool_size,validation_us\n");
+        for block in &self.blocks {
+            writeln!(
+                csv,
+                "{},{},{},{},{},{},{},{}",
+                block.height,
+                block.transactions,
+                block.spam_transactions,
+                block.size,
+                block.fullness,
+                block.notes,
+                block.mempool_size,
+                block.validation_time.as_micros(),
+            )
+            .unwrap();
+        }
+        csv
+    }
+}
+
+fn mean(values: impl Iterator<Item = f64>) -> Option<f64> {
+    let (sum, count) = values.fold((0.0, 0usize), |(sum, count), value| (sum + value, count + 1));
+    (count > 0).then(|| sum / count as f64)
+}
+
+/// Generates the transactions of a spam scenario and mines them.
+///
+/// ## Fields
+/// - `config`: The scenario parameters.
+/// - `context`: The context at the chain tip.
+/// - `mempool`: The transaction pool blocks are filled from.
+/// - `next_output`: Counter making every generated output distinct.
+/// - `submitted`: The height at which each pending honest transaction was submitted.
+/// - `rng`: The generator drawing block hashes, seeded from the config.
+#[derive(Debug, Clone)]
+pub struct SpamScenario {
+    pub config: SpamConfig,
+    pub context: BCContext,
+    pub mempool: Mempool,
+    pub next_output: usize,
+    pub submitted: HashMap<TxId, u32>,
+    pub rng: StdRng,
+}
+
+impl SpamScenario {
+    /// Creates a scenario starting from the given context.
+    pub fn new(config: SpamConfig, context: BCContext) -> Self {
+        Self {
+            config,
+            context,
+            mempool: Mempool::new(config.policy),
+            next_output: 0,
+            submitted: HashMap::new(),
+            rng: StdRng::seed_from_u64(config.seed),
+        }
+    }
+
+    /// Returns a fresh counter value.
+    ///
+    /// Transparent outputs are distinguished by their index and notes by their value,
+    /// so every output gets a distinct counter.
+    fn next_counter(&mut self) -> usize {
+        self.next_output += 1;
+        self.next_output
+    }
+
+    /// Creates a funded transparent output by applying a funding transaction directly
+    /// to the context.
+    fn fund(&mut self, address: Address, value: Amount) -> Result<TXO, ValidationError> {
+        let index = self.next_counter();
+        let txo = TXO { tx: BCTransaction::default(), index, value, address };
+        let funding = BCTransaction {
+            transparent_outputs: vec![txo.clone()],
+            issuance: value,
+            ..BCTransaction::default()
+        };
+        validation::validate_transaction(&self.context, &funding)?;
+        self.context.add_transaction(funding);
+        Ok(txo)
+    }
+
+    /// Returns a spam transaction creating `spam_outputs` notes.
+    fn spam_transaction(&mut self) -> Result<BCTransaction, ValidationError> {
+        let notes: Vec<Note> = (0..self.config.spam_outputs)
+            .map(|_| Note { value: Amount::const_from_i64(self.next_counter() as i64) })
+            .collect();
+        let value = notes.iter().map(|note| note.value).sum::<Option<Amount>>().and_then(|value| value + self.config.spam_fee);
+        let input = self.fund(SPAMMER, value.ok_or(ValidationError::AmountOutOfRange)?)?;
+        Ok(BCTransaction {
+            transparent_inputs: vec![input],
+            shielded_outputs: notes,
+            fee: self.config.spam_fee,
+            ..BCTransaction::default()
+        })
+    }
+
+    /// Returns an honest transparent payment paying the conventional fee.
+    fn honest_transaction(&mut self) -> Result<BCTransaction, ValidationError> {
+        let value = Amount::const_from_i64(100_000);
+        let output = TXO { tx: BCTransaction::default(), index: self.next_counter(), value, address: HONEST };
+        let shape = BCTransaction {
+            transparent_inputs: vec![output.clone()],
+            transparent_outputs: vec![output.clone()],
+            ..BCTransaction::default()
+        };
+        let fee = self.config.policy.conventional_fee(&shape);
+        let input = self.fund(HONEST, (value + fee).ok_or(ValidationError::AmountOutOfRange)?)?;
+        Ok(BCTransaction {
+            transparent_inputs: vec![input],
+            transparent_outputs: vec![output],
+            fee,
+            ..BCTransaction::default()
+        })
+    }
+
+    /// Runs the scenario.
+    ///
+    /// The inputs of generated transactions are created by funding transactions applied
+    /// directly to the context, so the context's coinbase maturity should be `0`.
+    ///
+    /// ## Returns
+    /// The report of the run, or the reason a funding transaction or mined block was invalid.
+    pub fn run(mut self) -> Result<SpamReport, ValidationError> {
+        let mut report = SpamReport { initial_notes: self.context.notes.len(), ..SpamReport::default() };
+        for _ in 0..self.config.blocks {
+            let height = self.context.height;
+            for _ in 0..self.config.spam_per_block {
+                let tx = self.spam_transaction()?;
+                if self.mempool.try_add_transaction(tx, &self.context).is_err() {
+                    report.rejected_spam += 1;
+                }
+            }
+            for _ in 0..self.config.honest_per_block {
+                let tx = self.honest_transaction()?;
+                let txid = tx.txid();
+                if self.mempool.add_transaction(tx, &self.context) {
+                    self.submitted.insert(txid, height);
+                }
+            }
+
+            let max_size = self.context.rules().max_block_size;
+            let block = BCBlock {
+                parent: None,
+                score: 1,
+                transactions: self.mempool.block_template(max_size.saturating_sub(BLOCK_HEADER_SIZE)),
+                hash: BlockHash::from_rng(&mut self.rng),
+                state_root: None,
+            };
+            let start = Instant::now();
+            validation::try_add_block(&mut self.context, &block)?;
+            let validation_time = start.elapsed();
+            self.mempool.remove_block(&block);
+
+            let mut spam_transactions = 0;
+            for tx in &block.transactions {
+                if tx.transparent_inputs.first().is_some_and(|txo| txo.address == SPAMMER) {
+                    spam_transactions += 1;
+                }
+                if let Some(submitted) = self.submitted.remove(&tx.txid()) {
+                    report.honest_latencies.push(height - submitted + 1);
+                }
+            }
+            report.blocks.push(BlockStats {
+                height,
+                transactions: block.transactions.len(),
+                spam_transactions,
+                size: block.size(),
+                fullness: block.size() as f64 / max_size as f64,
+                notes: self.context.notes.len(),
+                mempool_size: self.mempool.len(),
+                validation_time,
+            });
+        }
+        report.unconfirmed_honest = self.submitted.len();
+        Ok(report)
+    }
+}
@@ -1,12 +1,51 @@
 // src/traits.rs
-use crate::transaction::BCTransaction;
+use std::fmt::Debug;
 use crate::transaction::TXO;
 use crate::transaction::Note;
 use crate::context::BCContext;
+use crate::params::ConsensusRules;
+use crate::commitment::StateRoot;
+use crate::amount::Amount;
+use crate::validation::{self, ValidationError};
 
 
 /// Traits for best-chain protocol components.
-pub trait TransactionTrait {
+///
+/// `TransactionTrait` covers what the generic validation, block application and block
+/// tree need to know about a transaction, so that alternative transaction models can be
+/// plugged in without depending on the shape of their inputs and outputs.
+pub trait TransactionTrait: Clone + Debug + Send + Sync {
+    /// Get the fee.
+    fn fee(&self) -> Amount;
+    /// Get the issuance.
+    fn issuance(&self) -> Amount;
+    /// Check if this is a coinbase transaction.
+    fn is_coinbase(&self) -> bool;
+    /// Get the expiry height (`0` if the transaction never expires).
+    fn expiry_height(&self) -> u32;
+    /// Get the estimated serialized size in bytes.
+    fn size(&self) -> usize;
+
+    /// Check if the transaction has expired at the given height.
+    ///
+    /// An expiry height of `0` never expires, and nothing expires before the rules
+    /// enforce expiry.
+    fn is_expired(&self, rules: &ConsensusRules, height: u32) -> bool {
+        let expiry_height = self.expiry_height();
+        rules.expiry_enforced && expiry_height != 0 && height > expiry_height
+    }
+
+    /// Check the model-specific rules that do not depend on the ledger state.
+    ///
+    /// These checks run in parallel across the transactions of a block, so they must
+    /// not depend on earlier transactions.
+    fn check_structure(&self, _rules: &ConsensusRules) -> Result<(), ValidationError> {
+        Ok(())
+    }
+}
+
+/// Accessors for transactions with transparent and shielded inputs and outputs.
+pub trait ShieldedTransactionTrait: TransactionTrait {
     /// Get the transparent inputs.
     fn transparent_inputs(&self) -> &[TXO];
     /// Get the transparent outputs.
@@ -15,20 +54,40 @@ pub trait TransactionTrait {
     fn shielded_inputs(&self) -> &[Note];
     /// Get the shielded outputs.
     fn shielded_outputs(&self) -> &[Note];
-    /// Get the fee.
-    fn fee(&self) -> i32;
     /// Get the anchor (if any).
     fn anchor(&self) -> Option<&BCContext>;
-    /// Get the issuance.
-    fn issuance(&self) -> i32;
 }
 
 /// Traits for best-chain protocol components.
-pub trait ContextTrait {
-    /// Add a transaction to the context.
-    fn add_transaction(&mut self, tx: BCTransaction) -> bool;
-    /// Check if a note is spent.
-    fn is_spent(&self, note: &Note) -> bool;
+///
+/// `ContextTrait` is the ledger state of a transaction model. Implementations decide
+/// whether a transaction's inputs can be spent and how it changes the state; the rules
+/// shared by every model (fees, issuance, expiry, block limits) are checked by the
+/// [`validation`] module.
+pub trait ContextTrait: Clone + Debug {
+    /// The transaction model of this context.
+    type Transaction: TransactionTrait;
+
+    /// Get the height of the block transactions are being added to.
+    fn height(&self) -> u32;
+    /// Get the consensus rules in effect at the current height.
+    fn rules(&self) -> ConsensusRules;
+    /// Get the total issuance so far.
+    fn total_issuance(&self) -> Amount;
+    /// Check the model-specific validity of a transaction against the current state
+    /// (e.g. that its inputs are unspent).
+    fn check_transaction(&self, tx: &Self::Transaction) -> Result<(), ValidationError>;
+    /// Apply a transaction that has already been validated.
+    fn apply_transaction(&mut self, tx: Self::Transaction);
+    /// Advance to the next block height.
+    fn advance_height(&mut self);
     /// Copy the context (for forks).
     fn copy(&self) -> Self;
+    /// Get the commitment to the current state.
+    fn state_root(&self) -> StateRoot;
+
+    /// Add a transaction to the context.
+    fn add_transaction(&mut self, tx: Self::Transaction) -> bool {
+        validation::add_transaction(self, tx)
+    }
 }
@@ -5,6 +5,34 @@
 //! Transactions can include transparent and shielded inputs/outputs, fees, and issuance.
 //! They are validated against the current context to ensure correctness.
 
+use crate::commitment::{self, Digest, Encode};
+use std::collections::HashSet;
+use crate::context::BCContext;
+use crate::params::ConsensusRules;
+use crate::traits::{ShieldedTransactionTrait, TransactionTrait};
+use crate::amount::Amount;
+use crate::validation::{self, ValidationError};
+use serde::{Serialize, Deserialize};
+
+/// Number of blocks that must be mined on top of a coinbase transaction before its
+/// transparent outputs can be spent (Zcash's `COINBASE_MATURITY`).
+pub const COINBASE_MATURITY: u32 = 100;
+
+/// Estimated size in bytes of a transaction's fixed fields (header, counts and binding signature).
+pub const TX_BASE_SIZE: usize = 100;
+
+/// Estimated size in bytes of a transparent input (a standard P2PKH input).
+pub const TRANSPARENT_INPUT_SIZE: usize = 150;
+
+/// Estimated size in bytes of a transparent output (a standard P2PKH output).
+pub const TRANSPARENT_OUTPUT_SIZE: usize = 34;
+
+/// Estimated size in bytes of a shielded spend description.
+pub const SHIELDED_INPUT_SIZE: usize = 384;
+
+/// Estimated size in bytes of a shielded output description.
+pub const SHIELDED_OUTPUT_SIZE: usize = 948;
+
 /// Represents a transaction in the Best-Chain protocol.
 /// 
 /// A `BCTransaction` contains transparent and shielded inputs/outputs, a fee, an anchor, and issuance.
@@ -17,37 +45,60 @@
 /// - `fee`: The transaction fee.
 /// - `anchor`: Optional anchor to a prior context.
 /// - `issuance`: The amount of new coins issued by the transaction.
-
-use crate::context::Spentness;
-use crate::context::BCContext;
-use serde::{Serialize, Deserialize};
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// - `expiry_height`: The last height at which the transaction may be mined (`0` means it never expires).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[derive(Eq, Hash, PartialEq)]
 pub struct BCTransaction {
     pub transparent_inputs: Vec<TXO>,
     pub transparent_outputs: Vec<TXO>,
     pub shielded_inputs: Vec<Note>,
     pub shielded_outputs: Vec<Note>,
-    pub fee: i32,
+    pub fee: Amount,
     pub anchor: Option<BCContext>,
-    pub issuance: i32,
+    pub issuance: Amount,
+    pub expiry_height: u32,
 }
 
 /// Represents a transparent transaction output.
 /// 
-/// A `TXO` contains the transaction it belongs to, its index, and its value.
+/// A `TXO` contains a reference transaction and index, its value, and the address it pays to.
+/// The reference cannot be the transaction containing the output, whose id covers the output
+/// itself, so outputs are located through [`BCTransaction::outpoint`] instead.
 /// 
 /// ## Fields
-/// - `tx`: The transaction this output belongs to.
-/// - `index`: The index of this output in the transaction.
+/// - `tx`: A transaction distinguishing this output from otherwise identical ones.
+/// - `index`: An index distinguishing this output from otherwise identical ones.
 /// - `value`: The value of this output.
+/// - `address`: The address this output pays to.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[derive(Eq, Hash, PartialEq)]
 pub struct TXO {
     pub tx: BCTransaction,
     pub index: usize,
-    pub value: i32,
+    pub value: Amount,
+    pub address: Address,
+}
+
+/// A transparent address.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[derive(Eq, Hash, PartialEq, Ord, PartialOrd)]
+pub struct Address(pub u32);
+
+/// Identifier of a transaction: the digest of its contents.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Eq, Hash, PartialEq, Ord, PartialOrd)]
+pub struct TxId(pub Digest);
+
+/// Reference to a transparent output by the identifier of its transaction and its index.
+/// 
+/// ## Fields
+/// - `txid`: The identifier of the transaction the output belongs to.
+/// - `index`: The index of the output in the transaction.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Eq, Hash, PartialEq, Ord, PartialOrd)]
+pub struct OutPoint {
+    pub txid: TxId,
+    pub index: usize,
 }
 
 /// Represents a shielded note.
@@ -59,10 +110,44 @@ pub struct TXO {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[derive(Eq, Hash, PartialEq)]
 pub struct Note {
-    pub value: i32,
+    pub value: Amount,
 }
 
 // crates/bc/src/transaction.rs
+impl Encode for TXO {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.tx.encode(out);
+        self.index.encode(out);
+        self.value.encode(out);
+        self.address.encode(out);
+    }
+}
+
+impl Encode for Address {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.0.encode(out);
+    }
+}
+
+impl Encode for Note {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.value.encode(out);
+    }
+}
+
+impl Encode for BCTransaction {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.transparent_inputs.encode(out);
+        self.transparent_outputs.encode(out);
+        self.shielded_inputs.encode(out);
+        self.shielded_outputs.encode(out);
+        self.fee.encode(out);
+        self.anchor.encode(out);
+        self.issuance.encode(out);
+        self.expiry_height.encode(out);
+    }
+}
+
 impl BCTransaction {
     /// Validates the transaction against the given context.
     /// 
@@ -73,63 +158,136 @@ impl BCTransaction {
     /// - `true` if the transaction is valid.
     /// - `false` otherwise.
     pub fn is_valid(&self, context: &BCContext) -> bool {
-        println!("Validating transaction:");
-        println!("Is Coinbase: {}", self.is_coinbase());
-        println!("Fee: {}", self.fee);
-        println!("Issuance: {}", self.issuance);
-        
-        // Check if it's a coinbase transaction
-        let is_coinbase = self.is_coinbase();
-        
-        // Validate fee
-        if !is_coinbase && self.fee < 0 {
-            println!("Invalid transaction: Negative fee for non-coinbase transaction");
-            return false;
+        validation::is_valid_transaction(context, self)
+    }
+
+    /// Validates the transaction against the given context, returning the reason it is invalid.
+    pub fn validate(&self, context: &BCContext) -> Result<(), ValidationError> {
+        validation::validate_transaction(context, self)
+    }
+
+    /// Returns the identifier of the transaction.
+    pub fn txid(&self) -> TxId {
+        TxId(commitment::digest(self))
+    }
+
+    /// Returns the outpoint referring to the transparent output at `index` of this transaction.
+    pub fn outpoint(&self, index: usize) -> OutPoint {
+        OutPoint { txid: self.txid(), index }
+    }
+
+    /// Returns the sum of the transaction's transparent and shielded output values,
+    /// or `None` if it is out of range.
+    pub fn output_value(&self) -> Option<Amount> {
+        self.transparent_outputs
+            .iter()
+            .map(|txo| txo.value)
+            .chain(self.shielded_outputs.iter().map(|note| note.value))
+            .sum()
+    }
+    
+    /// Checks if the transaction is a coinbase transaction.
+    /// 
+    /// A coinbase transaction has no transparent or shielded inputs.
+    pub fn is_coinbase(&self) -> bool {
+        self.transparent_inputs.is_empty() && self.shielded_inputs.is_empty()
+    }
+
+    /// Returns the estimated serialized size of the transaction in bytes.
+    pub fn size(&self) -> usize {
+        TX_BASE_SIZE
+            + self.transparent_inputs.len() * TRANSPARENT_INPUT_SIZE
+            + self.transparent_outputs.len() * TRANSPARENT_OUTPUT_SIZE
+            + self.shielded_inputs.len() * SHIELDED_INPUT_SIZE
+            + self.shielded_outputs.len() * SHIELDED_OUTPUT_SIZE
+    }
+}
+
+impl TransactionTrait for BCTransaction {
+    fn fee(&self) -> Amount {
+        self.fee
+    }
+
+    fn issuance(&self) -> Amount {
+        self.issuance
+    }
+
+    fn is_coinbase(&self) -> bool {
+        BCTransaction::is_coinbase(self)
+    }
+
+    fn expiry_height(&self) -> u32 {
+        self.expiry_height
+    }
+
+    fn size(&self) -> usize {
+        BCTransaction::size(self)
+    }
+
+    fn check_structure(&self, rules: &ConsensusRules) -> Result<(), ValidationError> {
+        // Check that shielded transactions are allowed
+        let is_shielded = !self.shielded_inputs.is_empty() || !self.shielded_outputs.is_empty();
+        if is_shielded && !rules.shielded_enabled {
+            return Err(ValidationError::ShieldedNotActive);
         }
-        
-        // Validate issuance
-        if !is_coinbase && self.issuance != 0 {
-            println!("Invalid transaction: Non-zero issuance for non-coinbase transaction");
-            return false;
+        if self.is_coinbase() && !self.shielded_outputs.is_empty() && !rules.shielded_coinbase {
+            return Err(ValidationError::ShieldedCoinbaseNotActive);
         }
-        
-        // Check transparent inputs
-        for txo in &self.transparent_inputs {
-            if !context.utxo_set.contains(txo) {
-                println!("Invalid transaction: Transparent input not found in UTXO set");
-                return false;
+
+        // Check that output values are non-negative and their sum stays in range
+        let values = self.transparent_outputs.iter().map(|txo| txo.value)
+            .chain(self.shielded_outputs.iter().map(|note| note.value));
+        for value in values {
+            if value.is_negative() {
+                return Err(ValidationError::AmountOutOfRange);
             }
         }
-        
-        // Check shielded inputs
-        for note in &self.shielded_inputs {
-            if !context.notes.contains_key(note) || context.notes.get(note) != Some(&Spentness::Unspent) {
-                println!("Invalid transaction: Shielded input not found or already spent");
-                return false;
-            }
+        self.output_value().ok_or(ValidationError::AmountOutOfRange)?;
+
+        // Check that no input is spent twice
+        let transparent: HashSet<&TXO> = self.transparent_inputs.iter().collect();
+        let shielded: HashSet<&Note> = self.shielded_inputs.iter().collect();
+        if transparent.len() != self.transparent_inputs.len() || shielded.len() != self.shielded_inputs.len() {
+            return Err(ValidationError::DuplicateInput);
+        }
+
+        // Check that no note is created twice, since notes are identified by their contents
+        let outputs: HashSet<&Note> = self.shielded_outputs.iter().collect();
+        if outputs.len() != self.shielded_outputs.len() {
+            return Err(ValidationError::DuplicateNote);
         }
-        
+
         // Check if the transaction's anchor is valid
         if !self.shielded_inputs.is_empty() {
-            if let Some(context) = &self.anchor {
-                if !context.can_spend(&self.shielded_inputs) {
-                    println!("Invalid transaction: Cannot spend shielded inputs");
-                    return false;
-                }
-            } else {
-                println!("Invalid transaction: No anchor provided for shielded inputs");
-                return false;
+            match &self.anchor {
+                Some(anchor) if anchor.can_spend(&self.shielded_inputs) => {}
+                Some(_) => return Err(ValidationError::InvalidAnchor),
+                None => return Err(ValidationError::MissingAnchor),
             }
         }
-        
-        println!("Transaction is valid");
-        true
+
+        Ok(())
     }
-    
-    /// Checks if the transaction is a coinbase transaction.
-    /// 
-    /// A coinbase transaction has no transparent or shielded inputs.
-    fn is_coinbase(&self) -> bool {
-        self.transparent_inputs.is_empty() && self.shielded_inputs.is_empty()
+}
+
+impl ShieldedTransactionTrait for BCTransaction {
+    fn transparent_inputs(&self) -> &[TXO] {
+        &self.transparent_inputs
+    }
+
+    fn transparent_outputs(&self) -> &[TXO] {
+        &self.transparent_outputs
+    }
+
+    fn shielded_inputs(&self) -> &[Note] {
+        &self.shielded_inputs
+    }
+
+    fn shielded_outputs(&self) -> &[Note] {
+        &self.shielded_outputs
+    }
+
+    fn anchor(&self) -> Option<&BCContext> {
+        self.anchor.as_ref()
     }
 }
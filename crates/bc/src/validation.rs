@@ -0,0 +1,257 @@
+//! # Validation
+//!
+//! This module implements transaction validation and block application generically over
+//! [`ContextTrait`], so that every transaction model shares the same consensus rules.
+//!
+//! The rules checked here only use [`TransactionTrait`]: fees, issuance, expiry, amount
+//! ranges, block size and block subsidy. Spend checks are delegated to
+//! [`ContextTrait::check_transaction`]. Failures are reported as a [`ValidationError`];
+//! the `bool`-returning helpers log the error and discard it.
+//!
+//! Checks are split into context-free checks, which only need the consensus rules and
+//! height, and spend checks, which need the state left by the preceding transactions.
+//! When applying a block, the context-free checks run in parallel across its
+//! transactions with rayon; the spend checks and state updates stay sequential.
+
+use std::fmt;
+use rayon::prelude::*;
+use util::logging;
+use crate::amount::{Amount, AmountError};
+use crate::params::ConsensusRules;
+use crate::block::BCBlock;
+use crate::traits::{ContextTrait, TransactionTrait};
+
+/// Reason a transaction or block failed validation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationError {
+    /// A non-coinbase transaction has a negative fee.
+    NegativeFee,
+    /// A non-coinbase transaction issues new coins.
+    NonCoinbaseIssuance,
+    /// The transaction's expiry height is below the current height.
+    Expired { expiry_height: u32, height: u32 },
+    /// A sum of amounts left the range `-MAX_MONEY..=MAX_MONEY`.
+    AmountOutOfRange,
+    /// Shielded transactions are not active at the current height.
+    ShieldedNotActive,
+    /// Shielded coinbase outputs are not active at the current height.
+    ShieldedCoinbaseNotActive,
+    /// A transparent input is not in the UTXO set.
+    MissingInput,
+    /// A coinbase output is spent before it has matured.
+    ImmatureCoinbase,
+    /// The same input is spent twice in one transaction.
+    DuplicateInput,
+    /// A shielded input does not exist or has already been spent.
+    NoteUnavailable,
+    /// A shielded output re-creates a note that already exists.
+    DuplicateNote,
+    /// A transaction with shielded inputs has no anchor.
+    MissingAnchor,
+    /// The anchor does not contain the shielded inputs as unspent notes.
+    InvalidAnchor,
+    /// The block exceeds the maximum block size.
+    BlockTooLarge { size: usize, limit: usize },
+    /// The block issues more than the block subsidy.
+    ExcessiveIssuance,
+    /// The block commits to a state root other than the one obtained by applying it.
+    StateRootMismatch,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::NegativeFee => write!(f, "Negative fee for non-coinbase transaction"),
+            ValidationError::NonCoinbaseIssuance => write!(f, "Non-zero issuance for non-coinbase transaction"),
+            ValidationError::Expired { expiry_height, height } => {
+                write!(f, "Expired at height {} (current height {})", expiry_height, height)
+            }
+            ValidationError::AmountOutOfRange => write!(f, "Amount out of range"),
+            ValidationError::ShieldedNotActive => write!(f, "Shielded transactions are not active"),
+            ValidationError::ShieldedCoinbaseNotActive => write!(f, "Shielded coinbase outputs are not active"),
+            ValidationError::MissingInput => write!(f, "Transparent input not found in UTXO set"),
+            ValidationError::ImmatureCoinbase => write!(f, "Coinbase output spent before maturity"),
+            ValidationError::DuplicateInput => write!(f, "Input spent twice in the same transaction"),
+            ValidationError::NoteUnavailable => write!(f, "Shielded input not found or already spent"),
+            ValidationError::DuplicateNote => write!(f, "Shielded output already exists"),
+            ValidationError::MissingAnchor => write!(f, "No anchor provided for shielded inputs"),
+            ValidationError::InvalidAnchor => write!(f, "Cannot spend shielded inputs"),
+            ValidationError::BlockTooLarge { size, limit } => {
+                write!(f, "Size {} exceeds the limit of {} bytes", size, limit)
+            }
+            ValidationError::ExcessiveIssuance => write!(f, "Issuance exceeds block subsidy"),
+            ValidationError::StateRootMismatch => write!(f, "State root does not match"),
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+impl From<AmountError> for ValidationError {
+    fn from(_: AmountError) -> Self {
+        ValidationError::AmountOutOfRange
+    }
+}
+
+/// Checks the rules that do not depend on the ledger state.
+///
+/// ## Parameters
+/// - `rules`: The consensus rules in effect at `height`.
+/// - `height`: The height of the block the transaction is added to.
+/// - `tx`: The transaction to check.
+///
+/// ## Returns
+/// `Ok(())` if the transaction passes, or the first rule it breaks.
+pub fn check_context_free<T: TransactionTrait>(rules: &ConsensusRules, height: u32, tx: &T) -> Result<(), ValidationError> {
+    // Check if it's a coinbase transaction
+    let is_coinbase = tx.is_coinbase();
+
+    // Validate fee
+    if !is_coinbase && tx.fee().is_negative() {
+        return Err(ValidationError::NegativeFee);
+    }
+
+    // Validate issuance
+    if !is_coinbase && tx.issuance() != Amount::ZERO {
+        return Err(ValidationError::NonCoinbaseIssuance);
+    }
+
+    // Check expiry
+    if tx.is_expired(rules, height) {
+        return Err(ValidationError::Expired { expiry_height: tx.expiry_height(), height });
+    }
+
+    // Check the model-specific rules
+    tx.check_structure(rules)
+}
+
+/// Checks the rules that depend on the state left by the preceding transactions.
+///
+/// ## Returns
+/// `Ok(())` if the transaction passes, or the first rule it breaks.
+pub fn check_spends<C: ContextTrait>(context: &C, tx: &C::Transaction) -> Result<(), ValidationError> {
+    // Check that the total issuance stays in range
+    (context.total_issuance() + tx.issuance()).ok_or(ValidationError::AmountOutOfRange)?;
+
+    // Check the model-specific rules
+    context.check_transaction(tx)
+}
+
+/// Validates a transaction against the given context.
+///
+/// ## Parameters
+/// - `context`: The current context to validate against.
+/// - `tx`: The transaction to validate.
+///
+/// ## Returns
+/// `Ok(())` if the transaction is valid, or the first rule it breaks.
+pub fn validate_transaction<C: ContextTrait>(context: &C, tx: &C::Transaction) -> Result<(), ValidationError> {
+    check_context_free(&context.rules(), context.height(), tx)?;
+    check_spends(context, tx)
+}
+
+/// Checks the context-free rules for every transaction of a block in parallel.
+///
+/// ## Returns
+/// `Ok(())` if every transaction passes, or the error of the first one (in block
+/// order) that does not.
+pub fn check_block_context_free<C: ContextTrait>(context: &C, block: &BCBlock<C::Transaction>) -> Result<(), ValidationError> {
+    let rules = context.rules();
+    let height = context.height();
+    match block.transactions.par_iter().find_map_first(|tx| check_context_free(&rules, height, tx).err()) {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
+
+/// Validates a transaction against the given context, logging the reason if it is invalid.
+///
+/// ## Returns
+/// - `true` if the transaction is valid.
+/// - `false` otherwise.
+pub fn is_valid_transaction<C: ContextTrait>(context: &C, tx: &C::Transaction) -> bool {
+    match validate_transaction(context, tx) {
+        Ok(()) => true,
+        Err(err) => {
+            logging::log_debug(&format!("Invalid transaction: {}", err));
+            false
+        }
+    }
+}
+
+/// Adds a transaction to the context if it is valid.
+///
+/// ## Returns
+/// - `true` if the transaction is valid and successfully added.
+/// - `false` if the transaction is invalid.
+pub fn add_transaction<C: ContextTrait>(context: &mut C, tx: C::Transaction) -> bool {
+    if !is_valid_transaction(context, &tx) {
+        return false;
+    }
+    context.apply_transaction(tx);
+    true
+}
+
+/// Checks the block-level rules in effect at the context's height.
+///
+/// The transactions themselves are validated when the block is applied.
+pub fn validate_block<C: ContextTrait>(context: &C, block: &BCBlock<C::Transaction>) -> Result<(), ValidationError> {
+    let rules = context.rules();
+    if block.size() > rules.max_block_size {
+        return Err(ValidationError::BlockTooLarge { size: block.size(), limit: rules.max_block_size });
+    }
+
+    let issuance: Option<Amount> = block.transactions.iter().map(|tx| tx.issuance()).sum();
+    if issuance.ok_or(ValidationError::AmountOutOfRange)? > rules.block_subsidy {
+        return Err(ValidationError::ExcessiveIssuance);
+    }
+
+    Ok(())
+}
+
+/// Adds all transactions of a block to the context at the current height.
+///
+/// The block is validated against the rules in effect at the current height and
+/// applied atomically: if the block or any of its transactions is invalid, or the
+/// block commits to a state root other than the resulting one, the context is left
+/// unchanged. On success the height is advanced so that subsequent transactions
+/// belong to the next block.
+///
+/// The context-free checks of all transactions run in parallel before the spend checks,
+/// which are applied in block order.
+///
+/// ## Returns
+/// `Ok(())` if every transaction in the block was added, or the first rule broken.
+pub fn try_add_block<C: ContextTrait>(context: &mut C, block: &BCBlock<C::Transaction>) -> Result<(), ValidationError> {
+    validate_block(context, block)?;
+    check_block_context_free(context, block)?;
+
+    let mut next = context.copy();
+    for tx in &block.transactions {
+        check_spends(&next, tx)?;
+        next.apply_transaction(tx.clone());
+    }
+    if block.state_root.is_some_and(|root| root != next.state_root()) {
+        return Err(ValidationError::StateRootMismatch);
+    }
+    next.advance_height();
+    *context = next;
+    Ok(())
+}
+
+/// Adds all transactions of a block to the context, logging the reason if it is invalid.
+///
+/// See [`try_add_block`].
+///
+/// ## Returns
+/// - `true` if every transaction in the block was added.
+/// - `false` otherwise.
+pub fn add_block<C: ContextTrait>(context: &mut C, block: &BCBlock<C::Transaction>) -> bool {
+    match try_add_block(context, block) {
+        Ok(()) => true,
+        Err(err) => {
+            logging::log_debug(&format!("Invalid block: {}", err));
+            false
+        }
+    }
+}
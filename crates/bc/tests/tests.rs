@@ -2,9 +2,28 @@
 mod tests {
     use bc::transaction::Note;
     use bc::block::BlockHash;
-    use bc::block::BCBlock;
+    use bc::block::{BCBlock, BLOCK_HEADER_SIZE};
     use bc::context::BCContext;
-    use bc::transaction::{BCTransaction, TXO};
+    use bc::transaction::{Address, BCTransaction, TXO};
+    use bc::params::{ConsensusParams, NetworkUpgrade};
+    use bc::fees::{self, FeePolicy};
+    use bc::mempool::{Mempool, MempoolError, ReplacementPolicy};
+    use bc::chain::BlockTree;
+    use bc::params::ConsensusRules;
+    use bc::traits::{ContextTrait, TransactionTrait};
+    use bc::commitment::{self, Encode, StateRoot};
+    use bc::amount::{Amount, MAX_MONEY};
+    use bc::validation::{self, ValidationError};
+    use bc::index::IndexedChain;
+    use bc::metrics::{ChainMetrics, ObservedBlock};
+    use bc::arrivals::{simulate_mining, ArrivalProcess, MiningConfig, PoissonArrivals, TraceArrivals, TraceError};
+    use bc::anonymity::{anonymity_by_anchor_depth, AnonymityReport};
+    use bc::pos::{LeaderElection, PraosBlock, PraosParams, PraosSimulation};
+    use bc::spam::{SpamConfig, SpamScenario};
+    use bc::light::{BlockHeader, LightClient, TransactionProof, UtxoProof};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use std::collections::HashMap;
 
     // Helper to create a dummy BCTransaction for TXO purposes.
     fn dummy_bc_transaction() -> BCTransaction {
@@ -13,9 +32,10 @@ mod tests {
             transparent_outputs: vec![],
             shielded_inputs: vec![],
             shielded_outputs: vec![],
-            fee: 0,
+            fee: Amount::ZERO,
             anchor: None,
-            issuance: 0,
+            issuance: Amount::ZERO,
+            expiry_height: 0,
         }
     }
 
@@ -24,7 +44,8 @@ mod tests {
         TXO {
             tx: dummy_bc_transaction(),
             index: 0,
-            value,
+            value: Amount::const_from_i64(value as i64),
+            address: Address(0),
         }
     }
 
@@ -34,7 +55,7 @@ mod tests {
         assert!(ctx.transactions.is_empty());
         assert!(ctx.utxo_set.is_empty());
         assert!(ctx.notes.is_empty());
-        assert_eq!(ctx.total_issuance, 0);
+        assert_eq!(ctx.total_issuance, Amount::ZERO);
     }
 
     #[test]
@@ -45,9 +66,10 @@ mod tests {
             transparent_outputs: Vec::new(),
             shielded_inputs: Vec::new(),
             shielded_outputs: Vec::new(),
-            fee: 0,
+            fee: Amount::ZERO,
             anchor: None,
-            issuance: 0,
+            issuance: Amount::ZERO,
+            expiry_height: 0,
         };
         assert!(ctx.add_transaction(tx));
         assert_eq!(ctx.transactions.len(), 1);
@@ -63,16 +85,18 @@ mod tests {
             transparent_outputs: Vec::new(),
             shielded_inputs: Vec::new(),
             shielded_outputs: Vec::new(),
-            fee: 0,
+            fee: Amount::ZERO,
             anchor: None,
-            issuance: 0,
+            issuance: Amount::ZERO,
+            expiry_height: 0,
         };
 
         // Create a dummy TXO
         let dummy_txo = TXO {
             tx: dummy_tx,
             index: 0,
-            value: 100,
+            value: Amount::const_from_i64(100),
+            address: Address(0),
         };
 
         // Create a transaction with the dummy TXO to ensure it's not a coinbase transaction
@@ -81,9 +105,10 @@ mod tests {
             transparent_outputs: Vec::new(),
             shielded_inputs: Vec::new(),
             shielded_outputs: Vec::new(),
-            fee: -1,
+            fee: Amount::const_from_i64(-1),
             anchor: None,
-            issuance: 0,
+            issuance: Amount::ZERO,
+            expiry_height: 0,
         };
         
         println!("Attempting to add invalid transaction with fee: {}", tx.fee);
@@ -95,6 +120,7 @@ mod tests {
     }
     #[test]
     fn test_basic() {
+        let mut rng = StdRng::seed_from_u64(0);
         // Step 1: Create a BCContext.
         let mut ctx = BCContext::new();
 
@@ -105,15 +131,16 @@ mod tests {
             transparent_outputs: vec![dummy_txo(10)],
             shielded_inputs: vec![],
             shielded_outputs: vec![],
-            fee: 0,
+            fee: Amount::ZERO,
             anchor: None,
-            issuance: 10,
+            issuance: Amount::const_from_i64(10),
+            expiry_height: 0,
         };
 
         // Add coinbase_tx0 to the context.
         assert!(ctx.add_transaction(coinbase_tx0.clone()));
         // After adding coinbase_tx0, total issuance should be 10.
-        assert_eq!(ctx.total_issuance, 10);
+        assert_eq!(ctx.total_issuance, Amount::const_from_i64(10));
 
         // Step 3: Create the genesis block.
         // Python: genesis = BCBlock(None, 1, [coinbase_tx0])
@@ -121,12 +148,13 @@ mod tests {
             parent: None,
             score: 1,
             transactions: vec![coinbase_tx0.clone()],
-            hash: BlockHash::new(),
+            hash: BlockHash::from_rng(&mut rng),
+            state_root: None,
         };
 
         // Verify the genesis block's score and the context.
         assert_eq!(genesis.score, 1);
-        assert_eq!(ctx.total_issuance, 10);
+        assert_eq!(ctx.total_issuance, Amount::const_from_i64(10));
 
         // Step 4: Create coinbase_tx1 and spend_tx.
         // coinbase_tx1 = BCTransaction([], [6], [], [], -1, issuance=5)
@@ -135,9 +163,10 @@ mod tests {
             transparent_outputs: vec![dummy_txo(6)],
             shielded_inputs: vec![],
             shielded_outputs: vec![],
-            fee: -1,
+            fee: Amount::const_from_i64(-1),
             anchor: None,
-            issuance: 5,
+            issuance: Amount::const_from_i64(5),
+            expiry_height: 0,
         };
 
         // For spend_tx, we simulate consuming coinbase_tx0.transparent_output(0)
@@ -148,9 +177,10 @@ mod tests {
             transparent_outputs: vec![dummy_txo(9)],
             shielded_inputs: vec![],
             shielded_outputs: vec![],
-            fee: 1,
+            fee: Amount::const_from_i64(1),
             anchor: None,
-            issuance: 0,
+            issuance: Amount::ZERO,
+            expiry_height: 0,
         };
 
         // Add coinbase_tx1 and spend_tx.
@@ -162,12 +192,13 @@ mod tests {
             parent: Some(genesis.hash),
             score: 2,
             transactions: vec![coinbase_tx1.clone(), spend_tx.clone()],
-            hash: BlockHash::new(),
+            hash: BlockHash::from_rng(&mut rng),
+            state_root: None,
         };
 
         // After block1, total issuance should be 10 + 5 = 15.
         assert_eq!(block1.score, 2);
-        assert_eq!(ctx.total_issuance, 15);
+        assert_eq!(ctx.total_issuance, Amount::const_from_i64(15));
 
         // Step 5: Create coinbase_tx2 and shielding_tx.
         // coinbase_tx2 = BCTransaction([], [6], [], [], -1, issuance=5)
@@ -176,9 +207,10 @@ mod tests {
             transparent_outputs: vec![dummy_txo(6)],
             shielded_inputs: vec![],
             shielded_outputs: vec![],
-            fee: -1,
+            fee: Amount::const_from_i64(-1),
             anchor: None,
-            issuance: 5,
+            issuance: Amount::const_from_i64(5),
+            expiry_height: 0,
         };
 
         // For shielding_tx, we need coinbase_tx1.transparent_output(0) and spend_tx.transparent_output(0).
@@ -189,10 +221,11 @@ mod tests {
             transparent_inputs: vec![coinbase_tx1_output, spend_tx_output],
             transparent_outputs: vec![],
             shielded_inputs: vec![],
-            shielded_outputs: vec![Note { value: 8 }, Note { value: 6 }],
-            fee: 1,
+            shielded_outputs: vec![Note { value: Amount::const_from_i64(8) }, Note { value: Amount::const_from_i64(6) }],
+            fee: Amount::const_from_i64(1),
             anchor: None,
-            issuance: 0,
+            issuance: Amount::ZERO,
+            expiry_height: 0,
         };
 
         assert!(ctx.add_transaction(coinbase_tx2.clone()));
@@ -203,14 +236,15 @@ mod tests {
             parent: Some(block1.hash),
             score: 4,
             transactions: vec![coinbase_tx2.clone(), shielding_tx.clone()],
-            hash: BlockHash::new(),
+            hash: BlockHash::from_rng(&mut rng),
+            state_root: None,
         };
 
         // Simulate anchoring by copying the context.
         let block2_anchor = ctx.clone();
         assert_eq!(block2.score, 4);
         // Total issuance becomes 15 + 5 = 20.
-        assert_eq!(ctx.total_issuance, 20);
+        assert_eq!(ctx.total_issuance, Amount::const_from_i64(20));
 
         // Step 6: Create coinbase_tx3, shielded_tx, and deshielding_tx.
         // coinbase_tx3 = BCTransaction([], [7], [], [], -2, issuance=5)
@@ -219,9 +253,10 @@ mod tests {
             transparent_outputs: vec![dummy_txo(7)],
             shielded_inputs: vec![],
             shielded_outputs: vec![],
-            fee: -2,
+            fee: Amount::const_from_i64(-2),
             anchor: None,
-            issuance: 5,
+            issuance: Amount::const_from_i64(5),
+            expiry_height: 0,
         };
 
         // For shielded_tx, we simulate shielding_tx.shielded_output(0) as the first element.
@@ -229,10 +264,11 @@ mod tests {
             transparent_inputs: vec![],
             transparent_outputs: vec![],
             shielded_inputs: vec![],
-            shielded_outputs: vec![Note { value: 7 }],
-            fee: 1,
+            shielded_outputs: vec![Note { value: Amount::const_from_i64(7) }],
+            fee: Amount::const_from_i64(1),
             anchor: Some(block2_anchor.clone()),
-            issuance: 0,
+            issuance: Amount::ZERO,
+            expiry_height: 0,
         };
 
         // For deshielding_tx, simulate shielding_tx.shielded_output(1) as the second element.
@@ -241,9 +277,10 @@ mod tests {
             transparent_outputs: vec![dummy_txo(5)],
             shielded_inputs: vec![],
             shielded_outputs: vec![],
-            fee: 1,
+            fee: Amount::const_from_i64(1),
             anchor: Some(block2_anchor.clone()),
-            issuance: 0,
+            issuance: Amount::ZERO,
+            expiry_height: 0,
         };
 
         assert!(ctx.add_transaction(coinbase_tx3.clone()));
@@ -259,11 +296,1118 @@ mod tests {
                 shielded_tx.clone(),
                 deshielding_tx.clone(),
             ],
-            hash: BlockHash::new(),
+            hash: BlockHash::from_rng(&mut rng),
+            state_root: None,
         };
 
         // Total issuance becomes 20 + 5 = 25.
         assert_eq!(block3.score, 7);
-        assert_eq!(ctx.total_issuance, 25);
+        assert_eq!(ctx.total_issuance, Amount::const_from_i64(25));
+    }
+
+    #[test]
+    fn test_transaction_expiry() {
+        let mut ctx = BCContext::new();
+        ctx.height = 10;
+
+        let mut tx = dummy_bc_transaction();
+        tx.expiry_height = 10;
+        assert!(!tx.is_expired(&ctx.rules(), ctx.height));
+        assert!(tx.is_valid(&ctx));
+
+        // One block later the transaction can no longer be mined.
+        ctx.height = 11;
+        assert!(tx.is_expired(&ctx.rules(), ctx.height));
+        assert!(!ctx.add_transaction(tx.clone()));
+
+        // Nothing expires before the rules enforce expiry.
+        let rules = ConsensusRules { expiry_enforced: false, ..ctx.rules() };
+        assert!(!tx.is_expired(&rules, ctx.height));
+
+        // An expiry height of zero never expires.
+        tx.expiry_height = 0;
+        assert!(ctx.add_transaction(tx));
+    }
+
+    #[test]
+    fn test_coinbase_maturity() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut ctx = BCContext::new();
+        ctx.params.coinbase_maturity = 2;
+
+        let coinbase_tx = BCTransaction {
+            transparent_outputs: vec![dummy_txo(10)],
+            issuance: Amount::const_from_i64(10),
+            ..dummy_bc_transaction()
+        };
+        let spend_tx = BCTransaction {
+            transparent_inputs: vec![dummy_txo(10)],
+            transparent_outputs: vec![dummy_txo(9)],
+            fee: Amount::const_from_i64(1),
+            ..dummy_bc_transaction()
+        };
+
+        // Mine the coinbase at height 0.
+        let genesis = BCBlock {
+            parent: None,
+            score: 1,
+            transactions: vec![coinbase_tx],
+            hash: BlockHash::from_rng(&mut rng),
+            state_root: None,
+        };
+        assert!(ctx.add_block(&genesis));
+        assert_eq!(ctx.height, 1);
+
+        // At height 1 the coinbase output is still immature, and the failed
+        // block must leave the context untouched.
+        let block1 = BCBlock {
+            parent: Some(genesis.hash),
+            score: 1,
+            transactions: vec![spend_tx.clone()],
+            hash: BlockHash::from_rng(&mut rng),
+            state_root: None,
+        };
+        assert!(!ctx.is_mature(&dummy_txo(10)));
+        assert!(!ctx.add_block(&block1));
+        assert_eq!(ctx.height, 1);
+        assert_eq!(ctx.transactions.len(), 1);
+
+        // An empty block advances the chain to height 2, where it matures.
+        let empty = BCBlock {
+            parent: Some(genesis.hash),
+            score: 1,
+            transactions: vec![],
+            hash: BlockHash::from_rng(&mut rng),
+            state_root: None,
+        };
+        assert!(ctx.add_block(&empty));
+        assert!(ctx.is_mature(&dummy_txo(10)));
+        assert!(ctx.add_transaction(spend_tx));
+        assert!(ctx.coinbase_outputs.is_empty());
+    }
+
+    #[test]
+    fn test_network_upgrade_activation() {
+        // Activate Overwinter at genesis, everything up to NU6 at height 5,
+        // and the TFL at height 10.
+        let mut params = ConsensusParams::new();
+        for upgrade in NetworkUpgrade::ALL.into_iter().skip(1) {
+            params.set_activation_height(upgrade, Some(5));
+        }
+        params.set_activation_height(NetworkUpgrade::Tfl, Some(10));
+
+        assert_eq!(params.current_upgrade(4), Some(NetworkUpgrade::Overwinter));
+        assert_eq!(params.current_upgrade(5), Some(NetworkUpgrade::Nu6));
+        assert!(!params.rules_at(9).tfl_active);
+        assert!(params.rules_at(10).tfl_active);
+
+        let mut ctx = BCContext::with_params(params);
+        let shielding_tx = BCTransaction {
+            shielded_outputs: vec![Note { value: Amount::const_from_i64(3) }],
+            ..dummy_bc_transaction()
+        };
+
+        // Shielded transactions are rejected until Sapling activates.
+        ctx.height = 4;
+        assert!(!ctx.rules().shielded_enabled);
+        assert!(!shielding_tx.is_valid(&ctx));
+        ctx.height = 5;
+        assert!(ctx.rules().shielded_enabled);
+        assert!(ctx.add_transaction(shielding_tx));
+    }
+
+    #[test]
+    fn test_block_subsidy() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let params = ConsensusParams::mainnet();
+        let blossom = params.activation_height(NetworkUpgrade::Blossom).unwrap();
+
+        // The subsidy ramps up over the first 20,000 blocks.
+        assert_eq!(params.block_subsidy(0), Amount::ZERO);
+        assert_eq!(params.block_subsidy(1), Amount::const_from_i64(62_500));
+        assert_eq!(params.block_subsidy(9_999), Amount::const_from_i64(624_937_500));
+        assert_eq!(params.block_subsidy(10_000), Amount::const_from_i64(625_062_500));
+        assert_eq!(params.block_subsidy(19_999), Amount::const_from_i64(1_250_000_000));
+        assert_eq!(params.block_subsidy(20_000), Amount::const_from_i64(1_250_000_000));
+        assert_eq!(params.block_subsidy(blossom - 1), Amount::const_from_i64(1_250_000_000));
+        assert_eq!(params.block_subsidy(blossom), Amount::const_from_i64(625_000_000));
+
+        // The first halving happens after 840,000 pre-Blossom-equivalent blocks, shifted by
+        // half the slow start, at the Canopy activation height.
+        let halving = params.activation_height(NetworkUpgrade::Canopy).unwrap();
+        assert_eq!(halving, 1_046_400);
+        assert_eq!(params.halving(halving - 1), 0);
+        assert_eq!(params.halving(halving), 1);
+        assert_eq!(params.block_subsidy(halving - 1), Amount::const_from_i64(625_000_000));
+        assert_eq!(params.block_subsidy(halving), Amount::const_from_i64(312_500_000));
+
+        // A block may not issue more than its subsidy.
+        let mut ctx = BCContext::with_params(params);
+        let block = BCBlock {
+            parent: None,
+            score: 1,
+            transactions: vec![BCTransaction {
+                issuance: Amount::const_from_i64(1_250_000_001),
+                ..dummy_bc_transaction()
+            }],
+            hash: BlockHash::from_rng(&mut rng),
+            state_root: None,
+        };
+        assert!(!ctx.add_block(&block));
+        assert_eq!(ctx.height, 0);
+    }
+
+    #[test]
+    fn test_conventional_fee() {
+        // Two transparent inputs, one transparent output, three shielded outputs.
+        let tx = BCTransaction {
+            transparent_inputs: vec![dummy_txo(1), dummy_txo(2)],
+            transparent_outputs: vec![dummy_txo(3)],
+            shielded_outputs: vec![Note { value: Amount::const_from_i64(1) }, Note { value: Amount::const_from_i64(2) }, Note { value: Amount::const_from_i64(3) }],
+            ..dummy_bc_transaction()
+        };
+        assert_eq!(fees::logical_actions(&tx), 5);
+        assert_eq!(fees::conventional_fee(&tx), Amount::const_from_i64(25_000));
+
+        // Small transactions are charged for the grace actions.
+        let small = BCTransaction {
+            transparent_inputs: vec![dummy_txo(1)],
+            ..dummy_bc_transaction()
+        };
+        assert_eq!(fees::logical_actions(&small), 1);
+        assert_eq!(fees::conventional_fee(&small), Amount::const_from_i64(10_000));
+
+        let policy = FeePolicy::new();
+        let underpaying = BCTransaction { fee: Amount::const_from_i64(15_000), ..tx.clone() };
+        assert_eq!(policy.unpaid_actions(&underpaying), 2);
+        assert!(!policy.admits(&underpaying));
+        assert!(policy.admits(&BCTransaction { fee: Amount::const_from_i64(25_000), ..tx }));
+    }
+
+    #[test]
+    fn test_mempool_admission() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut ctx = BCContext::new();
+        let outputs: Vec<TXO> = (1..=3).map(|value| dummy_txo(value * 100_000)).collect();
+        let coinbase_tx = BCTransaction {
+            transparent_outputs: outputs.clone(),
+            issuance: Amount::const_from_i64(600_000),
+            ..dummy_bc_transaction()
+        };
+        assert!(ctx.add_transaction(coinbase_tx.clone()));
+
+        let spend = |txo: &TXO, fee: i64| BCTransaction {
+            transparent_inputs: vec![txo.clone()],
+            fee: Amount::const_from_i64(fee),
+            ..dummy_bc_transaction()
+        };
+
+        let mut mempool = Mempool::new(FeePolicy::new());
+        assert!(!mempool.add_transaction(coinbase_tx, &ctx));
+        assert!(!mempool.add_transaction(spend(&outputs[0], 5_000), &ctx));
+        assert!(mempool.add_transaction(spend(&outputs[0], 10_000), &ctx));
+        assert!(mempool.add_transaction(spend(&outputs[1], 30_000), &ctx));
+        assert!(mempool.add_transaction(spend(&outputs[2], 20_000), &ctx));
+        assert!(!mempool.add_transaction(spend(&outputs[2], 20_000), &ctx));
+        assert_eq!(mempool.len(), 3);
+
+        // Only two transactions fit, and the highest fee rates win.
+        let tx_size = spend(&outputs[0], 0).size();
+        let template = mempool.block_template(2 * tx_size);
+        assert_eq!(template.iter().map(|tx| tx.fee).collect::<Vec<_>>(), vec![Amount::const_from_i64(30_000), Amount::const_from_i64(20_000)]);
+
+        let block = BCBlock {
+            parent: None,
+            score: 1,
+            transactions: template,
+            hash: BlockHash::from_rng(&mut rng),
+            state_root: None,
+        };
+        assert!(ctx.add_block(&block));
+        mempool.remove_block(&block);
+        assert_eq!(mempool.len(), 1);
+        assert_eq!(mempool.revalidate(&ctx), 0);
+    }
+
+    #[test]
+    fn test_block_size_limit() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut params = ConsensusParams::new();
+        params.max_block_size = 2_000;
+        let mut ctx = BCContext::with_params(params);
+
+        let coinbase_tx = BCTransaction {
+            shielded_outputs: vec![Note { value: Amount::const_from_i64(1) }],
+            issuance: Amount::const_from_i64(1),
+            ..dummy_bc_transaction()
+        };
+        let mut block = BCBlock {
+            parent: None,
+            score: 1,
+            transactions: vec![coinbase_tx],
+            hash: BlockHash::from_rng(&mut rng),
+            state_root: None,
+        };
+        assert!(block.size() > 2_000);
+        assert!(!ctx.add_block(&block));
+
+        block.transactions[0].shielded_outputs.clear();
+        assert!(block.size() <= 2_000);
+        assert!(ctx.add_block(&block));
+    }
+
+    // A minimal account-based transaction model, used to check that validation
+    // and the block tree only depend on the bc traits.
+    #[derive(Debug, Clone)]
+    struct AccountTransaction {
+        from: Option<u32>,
+        to: u32,
+        amount: i32,
+        fee: i32,
+    }
+
+    impl TransactionTrait for AccountTransaction {
+        fn fee(&self) -> Amount {
+            Amount::const_from_i64(self.fee as i64)
+        }
+
+        fn issuance(&self) -> Amount {
+            if self.from.is_none() { Amount::const_from_i64(self.amount as i64) } else { Amount::ZERO }
+        }
+
+        fn is_coinbase(&self) -> bool {
+            self.from.is_none()
+        }
+
+        fn expiry_height(&self) -> u32 {
+            0
+        }
+
+        fn size(&self) -> usize {
+            100
+        }
+    }
+
+    #[derive(Debug, Clone, Default)]
+    struct AccountContext {
+        balances: HashMap<u32, i64>,
+        height: u32,
+        params: ConsensusParams,
+        total_issuance: Amount,
+    }
+
+    impl ContextTrait for AccountContext {
+        type Transaction = AccountTransaction;
+
+        fn height(&self) -> u32 {
+            self.height
+        }
+
+        fn rules(&self) -> ConsensusRules {
+            self.params.rules_at(self.height)
+        }
+
+        fn total_issuance(&self) -> Amount {
+            self.total_issuance
+        }
+
+        fn check_transaction(&self, tx: &AccountTransaction) -> Result<(), ValidationError> {
+            let funded = tx.from.is_none_or(|from| {
+                self.balances.get(&from).copied().unwrap_or(0) >= (tx.amount + tx.fee) as i64
+            });
+            if funded { Ok(()) } else { Err(ValidationError::MissingInput) }
+        }
+
+        fn apply_transaction(&mut self, tx: AccountTransaction) {
+            if let Some(from) = tx.from {
+                *self.balances.entry(from).or_default() -= (tx.amount + tx.fee) as i64;
+            }
+            *self.balances.entry(tx.to).or_default() += tx.amount as i64;
+            self.total_issuance = (self.total_issuance + tx.issuance()).unwrap();
+        }
+
+        fn advance_height(&mut self) {
+            self.height += 1;
+        }
+
+        fn copy(&self) -> Self {
+            self.clone()
+        }
+
+        fn state_root(&self) -> StateRoot {
+            let mut balances: Vec<_> = self.balances.iter().collect();
+            balances.sort();
+            StateRoot(commitment::digest(&balances))
+        }
+    }
+
+    #[test]
+    fn test_generic_transaction_model() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut ctx = AccountContext::default();
+        let mint = AccountTransaction { from: None, to: 1, amount: 50, fee: 0 };
+        let pay = AccountTransaction { from: Some(1), to: 2, amount: 30, fee: 1 };
+        let overdraft = AccountTransaction { from: Some(1), to: 2, amount: 30, fee: 1 };
+        let negative_fee = AccountTransaction { from: Some(2), to: 1, amount: 1, fee: -1 };
+
+        assert!(ctx.add_transaction(mint.clone()));
+        assert!(ctx.add_transaction(pay.clone()));
+        assert!(!ctx.add_transaction(overdraft));
+        assert!(!ctx.add_transaction(negative_fee));
+        assert_eq!(ctx.balances[&1], 19);
+        assert_eq!(ctx.balances[&2], 30);
+
+        // The same model can be used in a block tree.
+        let mut tree = BlockTree::new(AccountContext::default());
+        let genesis = BCBlock {
+            parent: None,
+            score: 1,
+            transactions: vec![mint, pay],
+            hash: BlockHash::from_rng(&mut rng),
+            state_root: None,
+        };
+        assert!(tree.add_block(genesis.clone()));
+        assert_eq!(tree.tip_context().height, 1);
+        assert_eq!(tree.tip_context().balances[&2], 30);
+    }
+
+    #[test]
+    fn test_block_tree_fork_choice() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut tree = BlockTree::new(BCContext::new());
+        let mut block = |parent: Option<BlockHash>, score: i32, transactions: Vec<BCTransaction>| BCBlock {
+            parent,
+            score,
+            transactions,
+            hash: BlockHash::from_rng(&mut rng),
+            state_root: None,
+        };
+
+        let coinbase_tx = BCTransaction {
+            transparent_outputs: vec![dummy_txo(10)],
+            issuance: Amount::const_from_i64(10),
+            ..dummy_bc_transaction()
+        };
+        let spend_tx = BCTransaction {
+            transparent_inputs: vec![dummy_txo(10)],
+            transparent_outputs: vec![dummy_txo(9)],
+            fee: Amount::const_from_i64(1),
+            ..dummy_bc_transaction()
+        };
+
+        let genesis = block(None, 1, vec![coinbase_tx]);
+        assert!(tree.add_block(genesis.clone()));
+        let second_genesis = block(None, 1, vec![]);
+        assert!(!tree.add_block(second_genesis.clone()));
+
+        // Two competing children of genesis: the first one seen wins the tie.
+        let a1 = block(Some(genesis.hash), 1, vec![spend_tx.clone()]);
+        let b1 = block(Some(genesis.hash), 1, vec![]);
+        assert!(tree.add_block(a1.clone()));
+        assert!(tree.add_block(b1.clone()));
+        assert_eq!(tree.tip.unwrap(), a1.hash);
+        assert!(tree.tip_context().utxo_set.contains(&dummy_txo(9)));
+
+        // Extending the other branch makes it the best chain, with its own context.
+        let b2 = block(Some(b1.hash), 1, vec![spend_tx.clone()]);
+        assert!(tree.add_block(b2.clone()));
+        assert_eq!(tree.best_chain(), vec![genesis.hash, b1.hash, b2.hash]);
+        assert_eq!(tree.get(&b2.hash).unwrap().height, 2);
+        assert_eq!(tree.get(&b2.hash).unwrap().total_score, 3);
+
+        // Invalid blocks and orphans are rejected.
+        assert!(!tree.add_block(block(Some(a1.hash), 1, vec![spend_tx])));
+        assert!(!tree.add_block(block(Some(second_genesis.hash), 1, vec![])));
+        assert_eq!(tree.len(), 4);
+    }
+
+    #[test]
+    fn test_state_root() {
+        let coinbase_a = BCTransaction {
+            transparent_outputs: vec![dummy_txo(4)],
+            issuance: Amount::const_from_i64(4),
+            ..dummy_bc_transaction()
+        };
+        let coinbase_b = BCTransaction {
+            shielded_outputs: vec![Note { value: Amount::const_from_i64(6) }],
+            issuance: Amount::const_from_i64(6),
+            ..dummy_bc_transaction()
+        };
+
+        // The same state reached in a different order has the same root.
+        let mut ctx1 = BCContext::new();
+        let mut ctx2 = BCContext::new();
+        assert_eq!(ctx1.state_root(), ctx2.state_root());
+        assert!(ctx1.add_transaction(coinbase_a.clone()));
+        assert_ne!(ctx1.state_root(), ctx2.state_root());
+        assert!(ctx1.add_transaction(coinbase_b.clone()));
+        assert!(ctx2.add_transaction(coinbase_b));
+        assert!(ctx2.add_transaction(coinbase_a));
+        assert_eq!(ctx1.state_root(), ctx2.state_root());
+        assert_eq!(ctx1.commitment.transparent_pool, 4);
+        assert_eq!(ctx1.commitment.shielded_pool, 6);
+
+        // Spending an output changes the root, and spending a note moves it
+        // to the nullifier set.
+        let anchor = ctx1.copy();
+        let spend_tx = BCTransaction {
+            transparent_inputs: vec![dummy_txo(4)],
+            shielded_inputs: vec![Note { value: Amount::const_from_i64(6) }],
+            shielded_outputs: vec![Note { value: Amount::const_from_i64(9) }],
+            fee: Amount::const_from_i64(1),
+            anchor: Some(anchor),
+            ..dummy_bc_transaction()
+        };
+        let before = ctx1.state_root();
+        assert!(ctx1.add_transaction(spend_tx));
+        assert_ne!(ctx1.state_root(), before);
+        assert!(ctx1.commitment.utxos.is_empty());
+        assert_eq!(ctx1.commitment.notes.len(), 2);
+        assert_eq!(ctx1.commitment.nullifiers.len(), 1);
+        assert_eq!(ctx1.commitment.shielded_pool, 9);
+
+        // Membership and non-membership proofs against the note tree.
+        let root = ctx1.commitment.notes.root();
+        let note = commitment::digest(&Note { value: Amount::const_from_i64(9) });
+        let missing = commitment::digest(&Note { value: Amount::const_from_i64(10) });
+        assert!(ctx1.commitment.notes.prove(&note).verify_membership(&root, &note));
+        assert!(!ctx1.commitment.notes.prove(&note).verify_non_membership(&root, &note));
+        assert!(ctx1.commitment.notes.prove(&missing).verify_non_membership(&root, &missing));
+        assert!(!ctx1.commitment.notes.prove(&missing).verify_membership(&root, &missing));
+
+        // Notes are identified by their contents, so re-creating a spent or unspent note, or
+        // creating the same note twice, is rejected rather than leaving the state root
+        // unchanged while the notes change.
+        let recreate = |value: i64| BCTransaction {
+            shielded_outputs: vec![Note { value: Amount::const_from_i64(value) }],
+            issuance: Amount::const_from_i64(value),
+            ..dummy_bc_transaction()
+        };
+        assert_eq!(validation::validate_transaction(&ctx1, &recreate(6)), Err(ValidationError::DuplicateNote));
+        assert_eq!(validation::validate_transaction(&ctx1, &recreate(9)), Err(ValidationError::DuplicateNote));
+        let twice = BCTransaction {
+            shielded_outputs: vec![Note { value: Amount::const_from_i64(5) }; 2],
+            issuance: Amount::const_from_i64(10),
+            ..dummy_bc_transaction()
+        };
+        assert_eq!(validation::validate_transaction(&ctx1, &twice), Err(ValidationError::DuplicateNote));
+        assert!(!ctx1.can_spend(&[Note { value: Amount::const_from_i64(6) }]));
+    }
+
+    #[test]
+    fn test_context_commitment() {
+        // Encodings are explicit, so they do not depend on the platform's `usize`.
+        let mut bytes = Vec::new();
+        (Amount::const_from_i64(1), Address(2), 3usize).encode(&mut bytes);
+        assert_eq!(bytes, [&1i64.to_le_bytes()[..], &2u32.to_le_bytes(), &3u64.to_le_bytes()].concat());
+        assert_eq!(commitment::digest(&3usize), commitment::digest(&3u64));
+
+        // Contexts with the same state root still differ in height and coinbase maturity.
+        let coinbase_tx = BCTransaction {
+            transparent_outputs: vec![dummy_txo(4)],
+            issuance: Amount::const_from_i64(4),
+            ..dummy_bc_transaction()
+        };
+        let mut ctx1 = BCContext::new();
+        assert!(ctx1.add_transaction(coinbase_tx));
+        let mut ctx2 = ctx1.copy();
+        ctx2.height += 1;
+        assert_eq!(ctx1.state_root(), ctx2.state_root());
+        assert_ne!(commitment::digest(&ctx1), commitment::digest(&ctx2));
+
+        let mut ctx3 = ctx1.copy();
+        ctx3.coinbase_outputs.insert(dummy_txo(4), 1);
+        assert_eq!(ctx1.state_root(), ctx3.state_root());
+        assert_ne!(commitment::digest(&ctx1), commitment::digest(&ctx3));
+        assert_eq!(commitment::digest(&ctx1), commitment::digest(&ctx1.copy()));
+    }
+
+    #[test]
+    fn test_block_state_root() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let coinbase_tx = BCTransaction {
+            transparent_outputs: vec![dummy_txo(10)],
+            issuance: Amount::const_from_i64(10),
+            ..dummy_bc_transaction()
+        };
+        let mut expected = BCContext::new();
+        assert!(expected.add_transaction(coinbase_tx.clone()));
+
+        let mut block = BCBlock {
+            parent: None,
+            score: 1,
+            transactions: vec![coinbase_tx],
+            hash: BlockHash::from_rng(&mut rng),
+            state_root: Some(BCContext::new().state_root()),
+        };
+        let mut ctx = BCContext::new();
+        assert!(!ctx.add_block(&block));
+
+        block.state_root = Some(expected.state_root());
+        assert!(ctx.add_block(&block));
+        assert_eq!(ctx.state_root(), expected.state_root());
+    }
+
+    #[test]
+    fn test_amount_range() {
+        let max = Amount::const_from_i64(MAX_MONEY);
+        assert!(Amount::from_i64(MAX_MONEY + 1).is_err());
+        assert!(Amount::from_i64(-MAX_MONEY - 1).is_err());
+        assert!(Amount::from_nonnegative_i64(-1).is_err());
+        assert_eq!(max + Amount::const_from_i64(1), None);
+        assert_eq!(-max - Amount::const_from_i64(1), None);
+        assert_eq!(vec![max, -max].into_iter().sum::<Option<Amount>>(), Some(Amount::ZERO));
+    }
+
+    #[test]
+    fn test_amount_out_of_range_errors() {
+        let mut ctx = BCContext::new();
+        let overflowing_outputs = BCTransaction {
+            transparent_outputs: vec![dummy_txo(0), dummy_txo(0)],
+            ..dummy_bc_transaction()
+        };
+        let mut tx = overflowing_outputs.clone();
+        tx.transparent_outputs[0].value = Amount::const_from_i64(MAX_MONEY);
+        tx.transparent_outputs[1].value = Amount::const_from_i64(1);
+        assert_eq!(tx.validate(&ctx), Err(ValidationError::AmountOutOfRange));
+
+        let mut negative_output = overflowing_outputs;
+        negative_output.transparent_outputs[0].value = Amount::const_from_i64(-1);
+        assert_eq!(negative_output.validate(&ctx), Err(ValidationError::AmountOutOfRange));
+
+        // Total issuance may not exceed MAX_MONEY.
+        ctx.total_issuance = Amount::const_from_i64(MAX_MONEY);
+        let coinbase_tx = BCTransaction {
+            issuance: Amount::const_from_i64(1),
+            ..dummy_bc_transaction()
+        };
+        assert_eq!(coinbase_tx.validate(&ctx), Err(ValidationError::AmountOutOfRange));
+    }
+
+    #[test]
+    fn test_chain_index() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut chain = IndexedChain::new(BCContext::new());
+        let mut block = |parent: Option<BlockHash>, score: i32, transactions: Vec<BCTransaction>| BCBlock {
+            parent,
+            score,
+            transactions,
+            hash: BlockHash::from_rng(&mut rng),
+            state_root: None,
+        };
+        let alice = Address(1);
+        let bob = Address(2);
+
+        let funding = TXO { address: alice, ..dummy_txo(10) };
+        let coinbase_tx = BCTransaction {
+            transparent_outputs: vec![funding.clone()],
+            issuance: Amount::const_from_i64(10),
+            ..dummy_bc_transaction()
+        };
+        let payment = TXO { address: bob, ..dummy_txo(9) };
+        let spend_tx = BCTransaction {
+            transparent_inputs: vec![funding.clone()],
+            transparent_outputs: vec![payment.clone()],
+            fee: Amount::const_from_i64(1),
+            ..dummy_bc_transaction()
+        };
+
+        let genesis = block(None, 1, vec![coinbase_tx.clone()]);
+        let a1 = block(Some(genesis.hash), 1, vec![spend_tx.clone()]);
+        assert!(chain.add_block(genesis.clone()));
+        assert!(chain.add_block(a1.clone()));
+
+        let index = &chain.index;
+        assert_eq!(index.transaction(&spend_tx.txid()), Some(&spend_tx));
+        assert_eq!(index.containing_block(&coinbase_tx.txid()), Some(genesis.hash));
+        assert_eq!(index.location(&spend_tx.txid()).unwrap().height, 1);
+        // Outpoints come from the creating transaction, so both outputs at index 0 differ.
+        let (funding_outpoint, payment_outpoint) = (coinbase_tx.outpoint(0), spend_tx.outpoint(0));
+        assert_ne!(funding_outpoint, payment_outpoint);
+        assert_eq!(index.outpoint(&funding), None);
+        assert_eq!(index.outpoint(&payment), Some(payment_outpoint));
+        assert_eq!(index.output(&funding_outpoint), Some(&funding));
+        assert_eq!(index.spender(&funding_outpoint), Some(spend_tx.txid()));
+        assert_eq!(index.spender(&payment_outpoint), None);
+        assert!(index.utxos_by_address(&alice).is_empty());
+        assert_eq!(index.utxos_by_address(&bob), vec![&payment]);
+        assert_eq!(index.balance(&bob), Some(Amount::const_from_i64(9)));
+
+        // A heavier fork without the spend disconnects it from the index.
+        let b1 = block(Some(genesis.hash), 2, vec![]);
+        assert!(chain.add_block(b1.clone()));
+        let index = &chain.index;
+        assert_eq!(index.transaction(&spend_tx.txid()), None);
+        assert_eq!(index.spender(&funding_outpoint), None);
+        assert_eq!(index.outpoint(&payment), None);
+        assert_eq!(index.outpoint(&funding), Some(funding_outpoint));
+        assert_eq!(index.utxos_by_address(&alice), vec![&funding]);
+        assert!(index.utxos_by_address(&bob).is_empty());
+
+        // Reorganizing back reconnects it.
+        let a2 = block(Some(a1.hash), 2, vec![]);
+        assert!(chain.add_block(a2.clone()));
+        assert_eq!(chain.tree.reorg_path(Some(&b1.hash), &a2.hash), (vec![b1.hash], vec![a1.hash, a2.hash]));
+        assert_eq!(chain.index.containing_block(&spend_tx.txid()), Some(a1.hash));
+        assert_eq!(chain.index.utxos_by_address(&bob), vec![&payment]);
+        assert_eq!(chain.index.balance(&alice), Some(Amount::ZERO));
+
+        // An output created and spent in the same block is gone once that block is
+        // disconnected, rather than restored as unspent.
+        let carol = Address(3);
+        let change = TXO { address: carol, ..dummy_txo(8) };
+        let forward_tx = BCTransaction {
+            transparent_inputs: vec![payment.clone()],
+            transparent_outputs: vec![change.clone()],
+            fee: Amount::const_from_i64(1),
+            ..dummy_bc_transaction()
+        };
+        let respend_tx = BCTransaction {
+            transparent_inputs: vec![change.clone()],
+            transparent_outputs: vec![TXO { address: alice, ..dummy_txo(7) }],
+            fee: Amount::const_from_i64(1),
+            ..dummy_bc_transaction()
+        };
+        let a3 = block(Some(a2.hash), 1, vec![forward_tx.clone(), respend_tx.clone()]);
+        assert!(chain.add_block(a3.clone()));
+        assert_eq!(chain.index.spender(&forward_tx.outpoint(0)), Some(respend_tx.txid()));
+        assert!(chain.index.utxos_by_address(&bob).is_empty());
+        assert!(chain.index.utxos_by_address(&carol).is_empty());
+
+        let b2 = block(Some(b1.hash), 3, vec![]);
+        assert!(chain.add_block(b2.clone()));
+        assert_eq!(chain.tree.tip, Some(b2.hash));
+        let index = &chain.index;
+        assert!(index.utxos_by_address(&carol).is_empty());
+        assert_eq!(index.output(&forward_tx.outpoint(0)), None);
+        assert_eq!(index.spender(&forward_tx.outpoint(0)), None);
+        assert_eq!(index.utxos_by_address(&alice), vec![&funding]);
+        assert!(index.utxos_by_address(&bob).is_empty());
+
+        // Equal outputs of different transactions keep their own outpoints.
+        let twin_tx = BCTransaction {
+            transparent_outputs: vec![funding.clone(), funding.clone()],
+            ..dummy_bc_transaction()
+        };
+        let b3 = block(Some(b2.hash), 1, vec![twin_tx.clone()]);
+        assert!(chain.add_block(b3));
+        assert_eq!(chain.index.output(&twin_tx.outpoint(0)), Some(&funding));
+        assert_eq!(chain.index.output(&twin_tx.outpoint(1)), Some(&funding));
+        assert_eq!(chain.index.output(&coinbase_tx.outpoint(0)), Some(&funding));
+    }
+
+    #[test]
+    fn test_parallel_block_validation() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut ctx = BCContext::new();
+        let outputs: Vec<TXO> = (0..4).map(|index| TXO { index, ..dummy_txo(10) }).collect();
+        let coinbase_tx = BCTransaction {
+            transparent_outputs: outputs.clone(),
+            issuance: Amount::const_from_i64(40),
+            ..dummy_bc_transaction()
+        };
+        assert!(ctx.add_transaction(coinbase_tx));
+
+        let spend = |inputs: Vec<TXO>| BCTransaction { transparent_inputs: inputs, ..dummy_bc_transaction() };
+        let mut block = |transactions: Vec<BCTransaction>| BCBlock {
+            parent: None,
+            score: 1,
+            transactions,
+            hash: BlockHash::from_rng(&mut rng),
+            state_root: None,
+        };
+
+        // Context-free errors are reported in block order, before any spend check.
+        let invalid = block(vec![
+            spend(vec![outputs[0].clone(), outputs[0].clone()]),
+            BCTransaction { fee: Amount::const_from_i64(-1), ..spend(vec![outputs[1].clone()]) },
+        ]);
+        assert_eq!(validation::check_block_context_free(&ctx, &invalid), Err(ValidationError::DuplicateInput));
+
+        // Spends within a block are still checked sequentially.
+        let double_spend = block(vec![spend(vec![outputs[2].clone()]), spend(vec![outputs[2].clone()])]);
+        assert_eq!(validation::check_block_context_free(&ctx, &double_spend), Ok(()));
+        assert_eq!(validation::try_add_block(&mut ctx, &double_spend), Err(ValidationError::MissingInput));
+
+        let valid = block(outputs.iter().map(|txo| spend(vec![txo.clone()])).collect());
+        assert_eq!(validation::try_add_block(&mut ctx, &valid), Ok(()));
+        assert!(ctx.utxo_set.is_empty());
+    }
+
+    #[test]
+    fn test_light_client() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut tree = BlockTree::new(BCContext::new());
+        let mut block = |parent: Option<BlockHash>, score: i32, transactions: Vec<BCTransaction>| BCBlock {
+            parent,
+            score,
+            transactions,
+            hash: BlockHash::from_rng(&mut rng),
+            state_root: None,
+        };
+
+        let funding = dummy_txo(10);
+        let coinbase_tx = BCTransaction {
+            transparent_outputs: vec![funding.clone()],
+            issuance: Amount::const_from_i64(10),
+            ..dummy_bc_transaction()
+        };
+        let spend_tx = BCTransaction {
+            transparent_inputs: vec![funding.clone()],
+            transparent_outputs: vec![dummy_txo(9)],
+            fee: Amount::const_from_i64(1),
+            ..dummy_bc_transaction()
+        };
+        let genesis = block(None, 1, vec![coinbase_tx]);
+        let a1 = block(Some(genesis.hash), 1, vec![spend_tx.clone()]);
+        let b1 = block(Some(genesis.hash), 1, vec![]);
+        let b2 = block(Some(b1.hash), 1, vec![]);
+
+        let mut client = LightClient::new();
+        for block in [&genesis, &a1] {
+            assert!(tree.add_block(block.clone()));
+            assert!(client.add_header(BlockHeader::from_entry(tree.get(&block.hash).unwrap())));
+        }
+        assert!(!client.add_header(BlockHeader::from_entry(tree.get(&a1.hash).unwrap())));
+
+        // The spend is included in the tip, and the funding output was unspent after genesis.
+        let tx_proof = TransactionProof::new(&a1, &spend_tx).unwrap();
+        assert_eq!(client.verify_transaction(&tx_proof), Some(1));
+        assert!(TransactionProof::new(&genesis, &spend_tx).is_none());
+        let utxo_proof = UtxoProof::new(tree.get(&genesis.hash).unwrap(), &funding);
+        assert_eq!(client.verify_utxo(&utxo_proof, &funding), Some(2));
+        assert_eq!(client.verify_utxo(&utxo_proof, &dummy_txo(9)), None);
+        let spent_proof = UtxoProof::new(tree.get(&a1.hash).unwrap(), &funding);
+        assert!(spent_proof.verify_not_unspent(&client.get(&a1.hash).unwrap().header, &funding));
+        assert_eq!(client.verify_utxo(&spent_proof, &funding), None);
+
+        // A tampered opening does not match the state root.
+        let mut forged = utxo_proof.clone();
+        forged.balances[0] += 1;
+        assert_eq!(client.verify_utxo(&forged, &funding), None);
+
+        // A heavier fork without the spend takes over the best chain.
+        for block in [&b1, &b2] {
+            assert!(tree.add_block(block.clone()));
+            assert!(client.add_header(BlockHeader::from_entry(tree.get(&block.hash).unwrap())));
+        }
+        assert_eq!(client.best_chain(), vec![genesis.hash, b1.hash, b2.hash]);
+        assert_eq!(client.best_chain(), tree.best_chain());
+        assert_eq!(client.verify_transaction(&tx_proof), None);
+        assert_eq!(client.confirmations(&genesis.hash), Some(3));
+    }
+
+    #[test]
+    fn test_chain_metrics() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut observed = |parent: Option<&ObservedBlock>, time: f64, honest: bool| ObservedBlock {
+            block: BCBlock {
+                parent: parent.map(|parent| parent.block.hash),
+                score: 1,
+                transactions: vec![],
+                hash: BlockHash::from_rng(&mut rng),
+                state_root: None,
+            },
+            time,
+            honest,
+        };
+
+        let genesis = observed(None, 0.0, true);
+        let a1 = observed(Some(&genesis), 1.0, true);
+        let b1 = observed(Some(&genesis), 2.0, false);
+        let b2 = observed(Some(&b1), 3.0, false);
+        let a2 = observed(Some(&a1), 4.0, true);
+        let a3 = observed(Some(&a2), 6.0, true);
+        let orphan_parent = observed(None, 5.0, false);
+        let orphan = observed(Some(&orphan_parent), 5.0, false);
+        let blocks = vec![genesis.clone(), a1.clone(), b1, b2, a2.clone(), orphan, a3.clone()];
+
+        let metrics = ChainMetrics::compute(&blocks, 2);
+        assert_eq!(metrics.total_blocks, 6);
+        assert_eq!(metrics.best_chain, vec![genesis.block.hash, a1.block.hash, a2.block.hash, a3.block.hash]);
+        assert_eq!(metrics.growth_rate, 0.5);
+        assert_eq!(metrics.orphan_rate, 2.0 / 6.0);
+        assert_eq!(metrics.chain_quality, 1.0);
+        assert_eq!(metrics.quality_windows.len(), 3);
+        assert_eq!(metrics.min_quality(), Some(1.0));
+        assert_eq!(metrics.reorg_depths, [(1, 1), (2, 1)].into_iter().collect());
+
+        assert!(metrics.summary_csv().contains("orphan_rate,0.3333333333333333\n"));
+        assert_eq!(metrics.reorg_csv(), "depth,count\n1,1\n2,1\n");
+        assert_eq!(metrics.quality_csv().lines().count(), 4);
+    }
+
+    #[test]
+    fn test_trace_arrivals() {
+        let path = std::env::temp_dir().join(format!("bc-trace-{}.csv", std::process::id()));
+        std::fs::write(&path, "height,timestamp\n1,1000\n2,1075\n\n3,1080\n4,1230\n").unwrap();
+        let trace = TraceArrivals::from_csv_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(trace.intervals, vec![75.0, 5.0, 150.0]);
+        assert_eq!(trace.mean_interval(), 230.0 / 3.0);
+
+        assert_eq!(TraceArrivals::from_csv_str("interval\n2.5\n7.5\n").unwrap().intervals, vec![2.5, 7.5]);
+        assert_eq!(TraceArrivals::from_csv_str("3\n4\n").unwrap().intervals, vec![3.0, 4.0]);
+        assert!(matches!(TraceArrivals::from_csv_str("interval\n1\nabc\n"), Err(TraceError::Parse { line: 3 })));
+        assert!(matches!(TraceArrivals::from_csv_str("time\n5\n4\n"), Err(TraceError::Negative { line: 3 })));
+        assert!(matches!(TraceArrivals::from_csv_str("a,b\n1,2\n"), Err(TraceError::MissingColumn)));
+        assert!(matches!(TraceArrivals::from_csv_str("interval\n"), Err(TraceError::Empty)));
+
+        let mut scaled = trace.clone().with_mean_interval(1.0).cycled();
+        assert_eq!(scaled.arrival_times(4).len(), 4);
+        let mut once = trace.clone();
+        assert_eq!(once.arrival_times(5), vec![75.0, 80.0, 230.0]);
+
+        // The second and third blocks arrive within the propagation delay and fork.
+        let config = MiningConfig { blocks: 10, propagation_delay: 10.0, honest_fraction: 1.0, seed: 7 };
+        let blocks = simulate_mining(&mut trace.clone(), &config);
+        assert_eq!(blocks.len(), 4);
+        assert_eq!(blocks[2].block.parent, blocks[1].block.parent);
+        let metrics = ChainMetrics::compute(&blocks, 2);
+        assert_eq!(metrics.best_chain.len(), 3);
+        assert_eq!(metrics.orphan_rate, 0.25);
+
+        // Poisson arrivals are reproducible from their seed.
+        let poisson = PoissonArrivals::new(75.0, 1).arrival_times(1000);
+        assert_eq!(poisson, PoissonArrivals::new(75.0, 1).arrival_times(1000));
+        assert!((poisson[999] / 1000.0 - 75.0).abs() < 10.0);
+    }
+
+    #[test]
+    fn test_mempool_replacement() {
+        let mut ctx = BCContext::new();
+        let outputs: Vec<TXO> = (1..=2).map(|value| dummy_txo(value * 100_000)).collect();
+        let coinbase_tx = BCTransaction {
+            transparent_outputs: outputs.clone(),
+            issuance: Amount::const_from_i64(300_000),
+            ..dummy_bc_transaction()
+        };
+        assert!(ctx.add_transaction(coinbase_tx));
+
+        let spend = |inputs: &[TXO], fee: i64| BCTransaction {
+            transparent_inputs: inputs.to_vec(),
+            fee: Amount::const_from_i64(fee),
+            ..dummy_bc_transaction()
+        };
+        let (a, b) = (spend(&outputs[..1], 10_000), spend(&outputs[1..], 12_000));
+        let double_spend = spend(&outputs, 40_000);
+
+        // First-seen keeps the original transactions.
+        let mut first_seen = Mempool::new(FeePolicy::new());
+        assert!(first_seen.add_transaction(a.clone(), &ctx));
+        assert_eq!(first_seen.try_add_transaction(double_spend.clone(), &ctx), Err(MempoolError::Conflict));
+
+        // Fee bumping requires the combined fee of the conflicts plus the increment.
+        let replacement = ReplacementPolicy::FeeBump { min_increment: Amount::const_from_i64(10_000) };
+        let mut pool = Mempool::with_replacement(FeePolicy::new(), replacement);
+        assert!(pool.add_transaction(a.clone(), &ctx));
+        assert!(pool.add_transaction(b.clone(), &ctx));
+        assert_eq!(pool.conflicts(&double_spend), vec![0, 1]);
+        assert_eq!(
+            pool.try_add_transaction(spend(&outputs, 25_000), &ctx),
+            Err(MempoolError::InsufficientFeeBump { required: Amount::const_from_i64(32_000) })
+        );
+        assert_eq!(pool.try_add_transaction(double_spend.clone(), &ctx), Ok(vec![a, b]));
+        assert_eq!(pool.transactions, vec![double_spend.clone()]);
+        assert_eq!(pool.evictions.len(), 2);
+        assert!(pool.evictions.iter().all(|eviction| eviction.replacement == double_spend.txid()));
+    }
+
+    #[test]
+    fn test_anonymity_sets() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let note = |value: i64| Note { value: Amount::const_from_i64(value) };
+        let mut block = |transactions: Vec<BCTransaction>| BCBlock {
+            parent: None,
+            score: 1,
+            transactions,
+            hash: BlockHash::from_rng(&mut rng),
+            state_root: None,
+        };
+        let spend = |input: Note, anchor: &BCContext| BCTransaction {
+            shielded_inputs: vec![input],
+            anchor: Some(anchor.clone()),
+            ..dummy_bc_transaction()
+        };
+
+        let genesis = BCContext::new();
+        let mut history = vec![genesis.clone()];
+        let mut blocks = vec![block(vec![BCTransaction {
+            shielded_outputs: (1..=4).map(note).collect(),
+            issuance: Amount::const_from_i64(10),
+            ..dummy_bc_transaction()
+        }])];
+        let mut ctx = genesis.clone();
+        assert!(ctx.add_block(&blocks[0]));
+        history.push(ctx.clone());
+
+        // Spend note 1 against the latest anchor, and create note 5.
+        let anchor0 = ctx.clone();
+        blocks.push(block(vec![
+            spend(note(1), &anchor0),
+            BCTransaction { shielded_outputs: vec![note(5)], issuance: Amount::const_from_i64(5), ..dummy_bc_transaction() },
+        ]));
+        assert!(ctx.add_block(&blocks[1]));
+        history.push(ctx.clone());
+
+        // An older anchor misses note 5, and note 1 has been spent since.
+        blocks.push(block(vec![spend(note(2), &anchor0), spend(note(3), &ctx)]));
+        assert!(ctx.add_block(&blocks[2]));
+        history.push(ctx.clone());
+
+        let report = AnonymityReport::from_chain(&genesis, &blocks);
+        let sets: Vec<(u32, usize, usize)> = report.spends.iter()
+            .map(|spend| (spend.anchor_age(), spend.anchor_notes, spend.anonymity_set))
+            .collect();
+        assert_eq!(sets, vec![(0, 4, 4), (1, 4, 3), (0, 4, 3)]);
+        assert_eq!(report.min_anonymity_set(), Some(3));
+        assert_eq!(report.mean_anonymity_set(), Some(10.0 / 3.0));
+        assert_eq!(report.to_csv().lines().nth(2), Some("2,1,1,4,3"));
+
+        // An invalid block, whose issuance overflows, ends the report instead of being applied.
+        let max_coinbase = |value: i64| BCTransaction {
+            shielded_outputs: vec![note(value)],
+            issuance: Amount::const_from_i64(MAX_MONEY),
+            ..dummy_bc_transaction()
+        };
+        let mut invalid_chain = blocks.clone();
+        invalid_chain.push(block(vec![spend(note(4), &ctx), max_coinbase(6), max_coinbase(7)]));
+        invalid_chain.push(block(vec![]));
+        assert_eq!(AnonymityReport::from_chain(&genesis, &invalid_chain), report);
+
+        // Deeper anchors give smaller anonymity sets.
+        assert_eq!(
+            anonymity_by_anchor_depth(&history, &[0, 1, 2, 4]),
+            vec![(0, Some(2.5)), (1, Some(5.0 / 3.0)), (2, Some(0.5)), (4, None)]
+        );
+    }
+
+    #[test]
+    fn test_praos_leader_election() {
+        let params = PraosParams { active_slot_coeff: 0.5, security_param: 5, beacon: 42 };
+        let election = LeaderElection::new(params, vec![1, 1, 2]);
+        assert_eq!(election.relative_stake(2), 0.5);
+        assert!((election.leader_probability(2) - (1.0 - 0.5f64.sqrt())).abs() < 1e-12);
+        assert_eq!(election.leaders(7), LeaderElection::new(params, vec![1, 1, 2]).leaders(7));
+        let schedules: Vec<usize> = (1..=1000).map(|slot| election.leaders(slot).len()).collect();
+        assert!(schedules.contains(&0));
+        assert!(schedules.iter().any(|leaders| *leaders > 1));
+        let active = schedules.iter().filter(|leaders| **leaders > 0).count();
+        assert!((400..600).contains(&active));
+
+        // Honest nodes agree on all but the last few blocks.
+        let mut sim = PraosSimulation::new(election, 1, BCContext::new());
+        sim.run(300);
+        let chains: Vec<Vec<BlockHash>> = sim.nodes.iter().map(|node| node.chain()).collect();
+        let shortest = chains.iter().map(|chain| chain.len()).min().unwrap();
+        assert!(shortest > 100);
+        let settled = shortest - params.security_param as usize;
+        assert!(chains.iter().all(|chain| chain[..settled] == chains[0][..settled]));
+        let node = &sim.nodes[sim.produced[1].leader];
+        let subsidy = BCContext::new().rules().block_subsidy.zatoshi();
+        assert_eq!(node.tip_context().total_issuance, Amount::const_from_i64(subsidy * node.height() as i64));
+        assert!(ChainMetrics::compute(&sim.observed_blocks(), 10).orphan_rate > 0.0);
+    }
+
+    #[test]
+    fn test_praos_bounded_rollback() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let params = PraosParams { active_slot_coeff: 0.9, security_param: 1, beacon: 1 };
+        let election = LeaderElection::new(params, vec![1]);
+        let mut leader_slots = (1..).filter(|slot| election.is_leader(*slot, 0));
+
+        let sim = PraosSimulation::new(election.clone(), 0, BCContext::new());
+        let mut node = sim.nodes[0].clone();
+        let mut fork = sim.nodes[0].clone();
+        let (s1, s2, s3, s4, s5) = (
+            leader_slots.next().unwrap(),
+            leader_slots.next().unwrap(),
+            leader_slots.next().unwrap(),
+            leader_slots.next().unwrap(),
+            leader_slots.next().unwrap(),
+        );
+
+        let a1 = node.propose(s1, BlockHash::from_rng(&mut rng));
+        assert!(node.receive(&a1, &election));
+        let a2 = node.propose(s2, BlockHash::from_rng(&mut rng));
+        assert!(node.receive(&a2, &election));
+
+        // Blocks from parties without stake are not accepted.
+        let unstaked = node.propose(s3, BlockHash::from_rng(&mut rng));
+        assert!(!node.receive(&unstaked, &LeaderElection::new(params, vec![0, 1])));
+
+        // A longer fork that would roll back two blocks is not adopted.
+        for slot in [s3, s4, s5] {
+            let block = fork.propose(slot, BlockHash::from_rng(&mut rng));
+            assert!(fork.receive(&block, &election));
+            assert!(node.receive(&block, &election));
+        }
+        assert_eq!(node.chain().last(), Some(&a2.block.hash));
+        assert_eq!(node.rejected_forks, 1);
+
+        // Blocks must come from a later slot than their parent.
+        let stale = PraosBlock { slot: s1, ..node.propose(s1, BlockHash::from_rng(&mut rng)) };
+        assert!(!node.receive(&stale, &election));
+    }
+
+    #[test]
+    fn test_spam_scenario() {
+        // Room for four spam transactions and no honest transaction besides them.
+        let mut ctx = BCContext::new();
+        ctx.params.max_block_size = BLOCK_HEADER_SIZE + 4 * 4_042 + 200;
+        let config = SpamConfig {
+            blocks: 5,
+            spam_per_block: 6,
+            spam_outputs: 4,
+            spam_fee: Amount::const_from_i64(1_000),
+            honest_per_block: 2,
+            policy: FeePolicy::new(),
+            seed: 7,
+        };
+
+        // ZIP-317 refuses underpaying spam, so honest transactions confirm immediately.
+        let report = SpamScenario::new(config, ctx.clone()).run().unwrap();
+        assert_eq!(report.rejected_spam, 30);
+        assert_eq!(report.blocks.last().unwrap().notes, 0);
+        assert_eq!(report.honest_latencies, vec![1; 10]);
+
+        // Spam matching the honest fee per logical action builds a backlog that crowds honest users out.
+        let config = SpamConfig { spam_fee: Amount::const_from_i64(50_000), ..config };
+        let report = SpamScenario::new(config, ctx.clone()).run().unwrap();
+        assert_eq!(report.rejected_spam, 0);
+        assert!(report.mean_fullness().unwrap() > 0.9);
+        assert_eq!(report.blocks[0].spam_transactions, 4);
+        assert!(report.mean_honest_latency().unwrap() > 1.0);
+        assert!(report.notes_growth().unwrap() > 10.0);
+        assert!(report.blocks.last().unwrap().mempool_size > report.blocks[0].mempool_size);
+        assert_eq!(report.to_csv().lines().count(), 6);
+
+        // Note growth excludes notes that existed before the run.
+        let mut noted = ctx.clone();
+        assert!(noted.add_transaction(BCTransaction {
+            shielded_outputs: vec![Note { value: Amount::const_from_i64(1_000_000) }],
+            issuance: Amount::const_from_i64(1_000_000),
+            ..dummy_bc_transaction()
+        }));
+        let rerun = SpamScenario::new(config, noted).run().unwrap();
+        assert_eq!(rerun.initial_notes, 1);
+        assert_eq!(rerun.notes_growth(), report.notes_growth());
+        assert_eq!(rerun.honest_latencies, report.honest_latencies);
+
+        // A block the context rejects is reported instead of panicking.
+        let mut tiny = ctx;
+        tiny.params.max_block_size = 0;
+        let config = SpamConfig { blocks: 1, spam_per_block: 0, honest_per_block: 0, ..config };
+        assert_eq!(SpamScenario::new(config, tiny).run(), Err(ValidationError::BlockTooLarge { size: BLOCK_HEADER_SIZE, limit: 0 }));
+    }
+
+    #[test]
+    fn test_block_hashes_are_seeded() {
+        let hashes = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            (0..4).map(|_| BlockHash::from_rng(&mut rng)).collect::<Vec<_>>()
+        };
+        assert_eq!(hashes(42), hashes(42));
+        assert_ne!(hashes(42)[0], hashes(43)[0]);
     }
 }
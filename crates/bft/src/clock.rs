@@ -0,0 +1,38 @@
+//! # Clock Module
+//!
+//! This module implements the global simulated clock.
+//!
+//! The clock is owned by the `Simulation` and advances to the timestamp of each event as it
+//! is processed. Nodes read it through their `Context`, and everything they send or schedule
+//! is timed relative to it.
+
+/// The global simulated clock.
+///
+/// ## Fields
+/// - `now`: The current simulated time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Clock {
+    now: u64,
+}
+
+impl Clock {
+    /// Creates a new clock at time 0.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the current simulated time.
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+
+    /// Advances the clock to the given time.
+    ///
+    /// The clock never goes backwards, so an earlier time leaves it unchanged.
+    ///
+    /// ## Parameters
+    /// - `time`: The time to advance to.
+    pub fn advance_to(&mut self, time: u64) {
+        self.now = self.now.max(time);
+    }
+}
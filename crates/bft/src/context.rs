@@ -0,0 +1,189 @@
+//! # Context Module
+//!
+//! This module defines the context handed to nodes while they react to an event.
+//!
+//! A node never touches the `Network` or the `EventQueue` directly. Instead it sends messages
+//! and sets or cancels timers through its `Context`, and the simulation routes them once the node
+//! is done, applying the network delay to messages and scheduling timers in the event queue.
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use crate::event_queue::TimerId;
+use crate::message::{Message, SignedVote};
+
+/// A timer set through a context.
+///
+/// ## Fields
+/// - `id`: The ID of the timer, used to cancel it.
+/// - `delay`: The simulated time until the timer fires.
+/// - `tag`: The value handed back to the node when the timer fires.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timer {
+    pub id: TimerId,
+    pub delay: u64,
+    pub tag: u64,
+}
+
+/// The node-side handle to the simulation.
+///
+/// ## Fields
+/// - `id`: The ID of the node the context belongs to.
+/// - `now`: The current simulated time.
+/// - `nodes`: The number of nodes a broadcast reaches.
+/// - `outbox`: The messages sent, with their targets, not yet handed to the network.
+/// - `timers`: The timers set, not yet scheduled.
+/// - `cancelled`: The timers cancelled, not yet removed from the event queue.
+/// - `next_timer_id`: The ID given to the next timer set.
+/// - `rng`: The random number generator for the node's random choices.
+#[derive(Debug, Clone)]
+pub struct Context {
+    id: usize,
+    now: u64,
+    nodes: usize,
+    outbox: Vec<(usize, Message)>,
+    timers: Vec<Timer>,
+    cancelled: Vec<TimerId>,
+    next_timer_id: TimerId,
+    rng: StdRng,
+}
+
+impl Context {
+    /// Creates a new context.
+    ///
+    /// ## Parameters
+    /// - `id`: The ID of the node the context belongs to.
+    /// - `now`: The current simulated time.
+    /// - `nodes`: The number of nodes a broadcast reaches, with IDs `0..nodes`.
+    ///
+    /// ## Returns
+    /// A new `Context` instance with nothing sent, no timers set and a generator seeded with 0.
+    pub fn new(id: usize, now: u64, nodes: usize) -> Self {
+        Self::with_state(id, now, nodes, 0, StdRng::seed_from_u64(0))
+    }
+
+    /// Creates a new context whose timers get IDs starting at `next_timer_id`, drawing random
+    /// choices from `rng`.
+    ///
+    /// The simulation uses this to keep timer IDs unique across contexts, and to derive every
+    /// node's randomness from its seed.
+    pub(crate) fn with_state(id: usize, now: u64, nodes: usize, next_timer_id: TimerId, rng: StdRng) -> Self {
+        Self {
+            id,
+            now,
+            nodes,
+            outbox: Vec::new(),
+            timers: Vec::new(),
+            cancelled: Vec::new(),
+            next_timer_id,
+            rng,
+        }
+    }
+
+    /// Returns the ID of the node the context belongs to.
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Returns the current simulated time.
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+
+    /// Returns the random number generator for the node's random choices.
+    ///
+    /// It is derived from the simulation's seed, so choices made through it are reproducible.
+    pub fn rng(&mut self) -> &mut StdRng {
+        &mut self.rng
+    }
+
+    /// Signs a vote as this node.
+    ///
+    /// ## Parameters
+    /// - `epoch`: The epoch of the proposal voted for.
+    /// - `parent_epoch`: The epoch of the block the proposal extends.
+    pub fn sign_vote(&self, epoch: usize, parent_epoch: usize) -> SignedVote {
+        SignedVote::new(self.id, epoch, parent_epoch)
+    }
+
+    /// Sends a message to a node.
+    ///
+    /// The message is stamped with this node as sender and the current time; the network
+    /// adds its delay when the message is routed.
+    ///
+    /// ## Parameters
+    /// - `to`: The ID of the target node.
+    /// - `message`: The message to send.
+    pub fn send(&mut self, to: usize, mut message: Message) {
+        message.sender = self.id;
+        message.timestamp = self.now;
+        self.outbox.push((to, message));
+    }
+
+    /// Sends a message to every node, including this one.
+    ///
+    /// ## Parameters
+    /// - `message`: The message to broadcast.
+    pub fn broadcast(&mut self, message: Message) {
+        for to in 0..self.nodes {
+            self.send(to, message.clone());
+        }
+    }
+
+    /// Sets a timer that fires on this node after a delay.
+    ///
+    /// ## Parameters
+    /// - `delay`: The simulated time until the timer fires.
+    /// - `tag`: A value handed back to the node when the timer fires.
+    ///
+    /// ## Returns
+    /// The ID of the timer, which can be passed to `cancel_timer`.
+    pub fn set_timer(&mut self, delay: u64, tag: u64) -> TimerId {
+        let id = self.next_timer_id;
+        self.next_timer_id += 1;
+        self.timers.push(Timer { id, delay, tag });
+        id
+    }
+
+    /// Cancels a timer set by this node, so that it never fires.
+    ///
+    /// ## Parameters
+    /// - `id`: The ID returned when the timer was set.
+    pub fn cancel_timer(&mut self, id: TimerId) {
+        self.cancelled.push(id);
+    }
+
+    /// Returns the messages sent so far, with their targets.
+    pub fn sent(&self) -> &[(usize, Message)] {
+        &self.outbox
+    }
+
+    /// Returns the timers set so far.
+    pub fn timers(&self) -> &[Timer] {
+        &self.timers
+    }
+
+    /// Returns the timers cancelled so far.
+    pub fn cancelled(&self) -> &[TimerId] {
+        &self.cancelled
+    }
+
+    /// Returns the ID the next timer set will get.
+    pub(crate) fn next_timer_id(&self) -> TimerId {
+        self.next_timer_id
+    }
+
+    /// Takes the sent messages, leaving the outbox empty.
+    pub(crate) fn take_sent(&mut self) -> Vec<(usize, Message)> {
+        std::mem::take(&mut self.outbox)
+    }
+
+    /// Takes the set timers, leaving none pending.
+    pub(crate) fn take_timers(&mut self) -> Vec<Timer> {
+        std::mem::take(&mut self.timers)
+    }
+
+    /// Takes the cancelled timers, leaving none pending.
+    pub(crate) fn take_cancelled(&mut self) -> Vec<TimerId> {
+        std::mem::take(&mut self.cancelled)
+    }
+}
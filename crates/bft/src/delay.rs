@@ -0,0 +1,129 @@
+//! # Delay Module
+//!
+//! This module implements the models the network uses to decide message latency.
+//!
+//! A `DelayModel` draws the delay of each message from the simulation's random number
+//! generator, so that protocol code only sends and runs stay reproducible from their seed.
+//! Continuous distributions are rounded to whole simulated time units.
+
+use rand::rngs::StdRng;
+use rand::Rng;
+use std::f64::consts::PI;
+
+/// A model of message latency.
+pub trait DelayModel: Send {
+    /// Returns the delay of a message sent from one node to another.
+    ///
+    /// ## Parameters
+    /// - `from`: The ID of the sender node.
+    /// - `to`: The ID of the target node.
+    /// - `rng`: The simulation's random number generator.
+    fn delay(&self, from: usize, to: usize, rng: &mut StdRng) -> u64;
+}
+
+/// The same delay for every message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConstantDelay(pub u64);
+
+impl DelayModel for ConstantDelay {
+    fn delay(&self, _from: usize, _to: usize, _rng: &mut StdRng) -> u64 {
+        self.0
+    }
+}
+
+/// Delays drawn uniformly from `min..=max`.
+///
+/// ## Fields
+/// - `min`: The smallest delay.
+/// - `max`: The largest delay.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UniformDelay {
+    pub min: u64,
+    pub max: u64,
+}
+
+impl UniformDelay {
+    /// Creates a uniform delay model over `min..=max`.
+    pub fn new(min: u64, max: u64) -> Self {
+        assert!(min <= max, "minimum delay exceeds maximum delay");
+        Self { min, max }
+    }
+}
+
+impl DelayModel for UniformDelay {
+    fn delay(&self, _from: usize, _to: usize, rng: &mut StdRng) -> u64 {
+        rng.gen_range(self.min..=self.max)
+    }
+}
+
+/// Exponentially distributed delays, as for messages queued at a busy link.
+///
+/// ## Fields
+/// - `mean`: The mean delay.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExponentialDelay {
+    pub mean: f64,
+}
+
+impl DelayModel for ExponentialDelay {
+    fn delay(&self, _from: usize, _to: usize, rng: &mut StdRng) -> u64 {
+        let u: f64 = rng.gen();
+        (-self.mean * (1.0 - u).ln()).round() as u64
+    }
+}
+
+/// Log-normally distributed delays, matching the heavy tail of measured internet latencies.
+///
+/// ## Fields
+/// - `mu`: The mean of the delay's logarithm.
+/// - `sigma`: The standard deviation of the delay's logarithm.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LogNormalDelay {
+    pub mu: f64,
+    pub sigma: f64,
+}
+
+impl LogNormalDelay {
+    /// Creates a log-normal delay model with the given median delay and `sigma`.
+    pub fn with_median(median: f64, sigma: f64) -> Self {
+        Self { mu: median.ln(), sigma }
+    }
+}
+
+impl DelayModel for LogNormalDelay {
+    fn delay(&self, _from: usize, _to: usize, rng: &mut StdRng) -> u64 {
+        // Box-Muller transform for a standard normal sample.
+        let u1: f64 = 1.0 - rng.gen::<f64>();
+        let u2: f64 = rng.gen();
+        let z = (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos();
+        (self.mu + self.sigma * z).exp().round() as u64
+    }
+}
+
+/// A fixed delay for each link, e.g. from measured round-trip times between regions.
+///
+/// ## Fields
+/// - `delays`: The delay from node `i` to node `j` at `delays[i][j]`.
+/// - `default`: The delay of links outside the matrix.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatrixDelay {
+    pub delays: Vec<Vec<u64>>,
+    pub default: u64,
+}
+
+impl MatrixDelay {
+    /// Creates a per-link delay model, with delay `default` for links outside the matrix.
+    pub fn new(delays: Vec<Vec<u64>>, default: u64) -> Self {
+        Self { delays, default }
+    }
+}
+
+impl DelayModel for MatrixDelay {
+    fn delay(&self, from: usize, to: usize, _rng: &mut StdRng) -> u64 {
+        self.delays
+            .get(from)
+            .and_then(|row| row.get(to))
+            .copied()
+            .unwrap_or(self.default)
+    }
+}
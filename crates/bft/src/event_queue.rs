@@ -1,36 +1,70 @@
 //! # Event Queue Module
 //!
-//! This module implements an event queue for processing messages in the BFT simulation.
+//! This module implements an event queue for processing messages, timers and wake-ups in the BFT simulation.
 //!
 //! The event queue is a priority queue that schedules events based on their timestamps.
 //! It ensures that events are processed in the correct order, maintaining causal consistency.
+//! Events at the same timestamp are ordered by sender, receiver and insertion sequence, so the
+//! order never depends on the heap's internal layout.
 
-use std::collections::BinaryHeap;
+use std::collections::{BinaryHeap, HashSet};
 use std::cmp::{Ord, PartialOrd, Ordering};
 use crate::message::Message;
 
 
+/// Identifies a timer, so that it can be cancelled before it fires.
+pub type TimerId = u64;
+
+/// What happens when an event is processed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EventKind {
+    /// A message is delivered to the receiver.
+    Deliver(Message),
+    /// A timer set by the receiver fires, handing back its tag.
+    Timer { id: TimerId, tag: u64 },
+    /// The receiver is woken up, e.g. to start running a protocol.
+    WakeUp,
+}
+
 /// Represents an event in the BFT simulation.
 /// 
-/// An `Event` contains a timestamp, sender ID, receiver ID, and a message.
+/// An `Event` contains a timestamp, sender ID, receiver ID, and what happens to the receiver.
 /// 
 /// ## Fields
-/// - `timestamp`: The logical timestamp of the event.
-/// - `sender`: The ID of the node sending the event.
+/// - `timestamp`: The simulated time of the event.
+/// - `sender`: The ID of the node causing the event.
 /// - `receiver`: The ID of the node receiving the event.
-/// - `message`: The content of the event.
+/// - `kind`: The delivery, timer or wake-up the event represents.
+/// - `seq`: The insertion sequence number, assigned by the queue when the event is scheduled.
 #[derive(Debug, Clone)]
 pub struct Event {
-    pub timestamp: u64, // Logical timestamp
+    pub timestamp: u64, // Simulated time
     pub sender: usize,  // Sender node ID
     pub receiver: usize, // Receiver node ID
-    pub message: Message, // Message content
+    pub kind: EventKind, // Delivery, timer or wake-up
+    pub seq: u64,       // Insertion sequence number
+}
+
+impl Event {
+    /// Returns the key events are ordered by: timestamp, then sender, receiver and insertion sequence.
+    fn key(&self) -> (u64, usize, usize, u64) {
+        (self.timestamp, self.sender, self.receiver, self.seq)
+    }
+
+    /// Returns the delivered message, if the event is a delivery.
+    pub fn message(&self) -> Option<&Message> {
+        match &self.kind {
+            EventKind::Deliver(message) => Some(message),
+            _ => None,
+        }
+    }
 }
 
-// Implement ordering for the event queue (min-heap)
+// Implement ordering for the event queue (min-heap). Ties in timestamp are broken by sender,
+// receiver and insertion sequence, so that runs are deterministic.
 impl PartialEq for Event {
     fn eq(&self, other: &Self) -> bool {
-        self.timestamp == other.timestamp
+        self.key() == other.key()
     }
 }
 
@@ -45,15 +79,18 @@ impl PartialOrd for Event {
 impl Ord for Event {
     fn cmp(&self, other: &Self) -> Ordering {
         // Reverse the order to make it a min-heap
-        other.timestamp.cmp(&self.timestamp)
+        other.key().cmp(&self.key())
     }
 }
 
 /// Represents the event queue for the BFT simulation.
 /// 
 /// The `EventQueue` is implemented as a binary heap to efficiently process events in order of their timestamps.
+/// Cancelled timers stay in the heap and are skipped when they reach the front.
 pub struct EventQueue {
     queue: BinaryHeap<Event>,
+    cancelled: HashSet<TimerId>,
+    next_seq: u64,
 }
 
 impl Default for EventQueue {
@@ -70,25 +107,54 @@ impl EventQueue {
     pub fn new() -> Self {
         Self {
             queue: BinaryHeap::new(),
+            cancelled: HashSet::new(),
+            next_seq: 0,
         }
     }
 
-    /// Schedules an event in the queue.
+    /// Schedules an event in the queue, assigning its insertion sequence number.
     /// 
     /// ## Parameters
     /// - `event`: The event to schedule.
-    pub fn schedule(&mut self, event: Event) {
+    pub fn schedule(&mut self, mut event: Event) {
+        event.seq = self.next_seq;
+        self.next_seq += 1;
         self.queue.push(event);
     }
 
-    /// Processes the next event in the queue.
+    /// Cancels a scheduled timer, so that it never fires.
+    /// 
+    /// ## Parameters
+    /// - `id`: The ID of the timer to cancel.
+    pub fn cancel_timer(&mut self, id: TimerId) {
+        self.cancelled.insert(id);
+    }
+
+    /// Processes the next event in the queue, skipping cancelled timers.
     /// 
     /// ## Returns
     /// The next event in the queue, or `None` if the queue is empty.
     pub fn process_next_event(&mut self) -> Option<Event> {
+        self.skip_cancelled();
         self.queue.pop()
     }
 
+    /// Returns the timestamp of the next event, without processing it.
+    pub fn next_timestamp(&mut self) -> Option<u64> {
+        self.skip_cancelled();
+        self.queue.peek().map(|event| event.timestamp)
+    }
+
+    /// Drops cancelled timers from the front of the queue.
+    fn skip_cancelled(&mut self) {
+        while let Some(Event { kind: EventKind::Timer { id, .. }, .. }) = self.queue.peek() {
+            if !self.cancelled.remove(id) {
+                break;
+            }
+            self.queue.pop();
+        }
+    }
+
     /// Checks if the queue is empty.
     /// 
     /// ## Returns
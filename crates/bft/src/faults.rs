@@ -0,0 +1,51 @@
+//! # Faults Module
+//!
+//! This module describes the random faults a network link may inject into the messages it carries.
+//!
+//! Each message sent over a link with `LinkFaults` may be lost, duplicated, or given extra
+//! jitter on top of its usual delay, so that it overtakes or is overtaken by other messages.
+//! All choices are drawn from the simulation's random number generator.
+
+use rand::rngs::StdRng;
+use rand::Rng;
+
+/// The fault probabilities of a link.
+///
+/// ## Fields
+/// - `drop`: The probability that a message is lost.
+/// - `duplicate`: The probability that a message is delivered twice, each copy with its own delay.
+/// - `reorder`: The probability that a message gets extra jitter.
+/// - `max_jitter`: The largest extra jitter, drawn uniformly from `1..=max_jitter`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LinkFaults {
+    pub drop: f64,
+    pub duplicate: f64,
+    pub reorder: f64,
+    pub max_jitter: u64,
+}
+
+impl LinkFaults {
+    /// Creates fault probabilities for a link that only loses messages.
+    pub fn lossy(drop: f64) -> Self {
+        Self { drop, ..Self::default() }
+    }
+
+    /// Decides whether a message is lost.
+    pub fn drops(&self, rng: &mut StdRng) -> bool {
+        rng.gen_bool(self.drop)
+    }
+
+    /// Decides whether a message is duplicated.
+    pub fn duplicates(&self, rng: &mut StdRng) -> bool {
+        rng.gen_bool(self.duplicate)
+    }
+
+    /// Returns the extra jitter of a message, or `None` if it gets none.
+    pub fn jitter(&self, rng: &mut StdRng) -> Option<u64> {
+        if self.max_jitter > 0 && rng.gen_bool(self.reorder) {
+            Some(rng.gen_range(1..=self.max_jitter))
+        } else {
+            None
+        }
+    }
+}
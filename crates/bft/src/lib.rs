@@ -7,6 +7,14 @@
 //! - Event-driven simulation
 //! - Logical clocks for causal ordering
 //! - Support for honest, Byzantine, and passive nodes
+//! - A Streamlet node running the full protocol epoch by epoch
+//! - A node-side context for sending messages and setting timers
+//! - A global simulated clock with cancellable timers and wake-ups in the event queue
+//! - Deterministic event ordering and a single seeded random number generator per run
+//! - Pluggable network delay models: constant, uniform, exponential, log-normal and per-link
+//! - Scheduled network partitions that drop or hold messages until they heal
+//! - Per-link message loss, duplication and reordering faults
+//! - Partial synchrony with an adversarial scheduler before the Global Stabilization Time
 
 use std::fmt::Debug;
 use util::logging;
@@ -18,6 +26,12 @@ pub mod node;
 pub mod simulation;
 pub mod message;
 pub mod event_queue;
+pub mod context;
+pub mod clock;
+pub mod delay;
+pub mod partition;
+pub mod faults;
+pub mod synchrony;
 
 pub trait PermissionedBFT: Debug + Clone {}
 
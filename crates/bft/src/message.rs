@@ -1,5 +1,135 @@
-#[derive(Debug, Clone)]
+//! # Message Module
+//!
+//! This module defines the typed messages exchanged by nodes in the BFT simulation.
+//!
+//! A `Message` records its sender, the epoch it belongs to and a typed `Payload`, so nodes
+//! can tell a proposal from a vote without parsing its content.
+
+use std::fmt;
+
+/// The kind of a message, determined by its payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MessageKind {
+    Text,
+    Proposal,
+    Vote,
+}
+
+/// A vote for the proposal of `epoch` extending the block of `parent_epoch`, signed by `voter`.
+///
+/// Signatures are modelled by construction: the fields are private, and votes are only created
+/// through [`Context::sign_vote`](crate::context::Context::sign_vote), signed by the node owning
+/// the context. A vote can be relayed as is, but no node can sign for another or alter a vote.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SignedVote {
+    voter: usize,
+    epoch: usize,
+    parent_epoch: usize,
+}
+
+impl SignedVote {
+    /// Creates a vote signed by `voter`.
+    pub(crate) fn new(voter: usize, epoch: usize, parent_epoch: usize) -> Self {
+        Self { voter, epoch, parent_epoch }
+    }
+
+    /// Returns the ID of the node that signed the vote.
+    pub fn voter(&self) -> usize {
+        self.voter
+    }
+
+    /// Returns the epoch of the proposal voted for.
+    pub fn epoch(&self) -> usize {
+        self.epoch
+    }
+
+    /// Returns the epoch of the block the proposal voted for extends.
+    pub fn parent_epoch(&self) -> usize {
+        self.parent_epoch
+    }
+}
+
+/// The protocol payload of a message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Payload {
+    /// Free-form text, such as notices and application values.
+    Text(String),
+    /// A proposal for the message's epoch, extending the block of `parent_epoch`.
+    Proposal { parent_epoch: usize },
+    /// A signed vote, which may be relayed by nodes other than its voter.
+    Vote(SignedVote),
+}
+
+impl Payload {
+    /// Returns the kind of message carrying this payload.
+    pub fn kind(&self) -> MessageKind {
+        match self {
+            Payload::Text(_) => MessageKind::Text,
+            Payload::Proposal { .. } => MessageKind::Proposal,
+            Payload::Vote { .. } => MessageKind::Vote,
+        }
+    }
+}
+
+impl fmt::Display for Payload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Payload::Text(content) => write!(f, "{}", content),
+            Payload::Proposal { parent_epoch } => write!(f, "proposal on epoch {}", parent_epoch),
+            Payload::Vote(vote) => write!(
+                f,
+                "vote by {} for epoch {} on epoch {}",
+                vote.voter, vote.epoch, vote.parent_epoch
+            ),
+        }
+    }
+}
+
+/// Represents a message sent between nodes.
+///
+/// ## Fields
+/// - `sender`: The ID of the node that sent the message.
+/// - `kind`: The kind of the message, matching its payload.
+/// - `epoch`: The protocol epoch the message belongs to, or 0 outside epoch-based protocols.
+/// - `payload`: The protocol payload.
+/// - `timestamp`: The logical timestamp of the message.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Message {
-    pub content: String,
+    pub sender: usize,
+    pub kind: MessageKind,
+    pub epoch: usize,
+    pub payload: Payload,
     pub timestamp: u64,
-}
\ No newline at end of file
+}
+
+impl Message {
+    /// Creates a new message with a zero timestamp.
+    ///
+    /// ## Parameters
+    /// - `sender`: The ID of the sending node.
+    /// - `epoch`: The epoch the message belongs to.
+    /// - `payload`: The protocol payload, which determines the kind of the message.
+    ///
+    /// ## Returns
+    /// A new `Message` instance.
+    pub fn new(sender: usize, epoch: usize, payload: Payload) -> Self {
+        Self {
+            sender,
+            kind: payload.kind(),
+            epoch,
+            payload,
+            timestamp: 0,
+        }
+    }
+
+    /// Creates a text message outside of any epoch.
+    pub fn text(sender: usize, content: impl Into<String>) -> Self {
+        Self::new(sender, 0, Payload::Text(content.into()))
+    }
+
+    /// Returns the message with the given logical timestamp.
+    pub fn with_timestamp(mut self, timestamp: u64) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+}
@@ -3,15 +3,44 @@
 //! This module implements the communication layer for the BFT simulation.
 //!
 //! The `Network` struct facilitates communication between nodes by managing sender and receiver channels.
-//! It also includes an event queue for scheduling and processing messages with logical timestamps.
+//! It also includes an event queue for scheduling messages, timers and wake-ups at simulated times,
+//! a pluggable `DelayModel` deciding how long each message takes to arrive, scheduled
+//! `Partition`s splitting the nodes, `LinkFaults` losing, duplicating or reordering messages, and
+//! an optional `PartialSynchrony` model bounding delays after the Global Stabilization Time.
 
 
-use crate::event_queue::{Event, EventQueue};
+use crate::delay::{ConstantDelay, DelayModel};
+use crate::event_queue::{Event, EventKind, EventQueue, TimerId};
+use crate::faults::LinkFaults;
+use crate::partition::{HeldMessages, Partition};
+use crate::synchrony::PartialSynchrony;
 use crate::message::Message;
 use log::error;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use crossbeam_channel::{unbounded, Sender, Receiver};
+use rand::rngs::StdRng;
+
+/// Counts of the messages the network carried and the faults that affected them.
+/// 
+/// ## Fields
+/// - `sent`: The messages sent to an existing node, counting each duplicate copy.
+/// - `partition_dropped`: The messages dropped for crossing a partition boundary.
+/// - `partition_held`: The messages held until a partition healed.
+/// - `lost`: The messages lost by a faulty link.
+/// - `duplicated`: The messages a faulty link delivered twice.
+/// - `reordered`: The messages a faulty link gave extra jitter.
+/// - `adversary_scheduled`: The messages scheduled by the adversary before GST.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NetworkStats {
+    pub sent: u64,
+    pub partition_dropped: u64,
+    pub partition_held: u64,
+    pub lost: u64,
+    pub duplicated: u64,
+    pub reordered: u64,
+    pub adversary_scheduled: u64,
+}
 
 /// Represents the network for the BFT simulation.
 /// 
@@ -21,12 +50,24 @@ use crossbeam_channel::{unbounded, Sender, Receiver};
 /// - `senders`: A map of sender channels for each node.
 /// - `receivers`: A map of receiver channels for each node.
 /// - `event_queue`: The event queue for scheduling and processing messages.
+/// - `delay_model`: The model deciding the delay of each message sent with `send`.
+/// - `partitions`: The scheduled partitions.
+/// - `faults`: The fault probabilities of links without their own.
+/// - `link_faults`: The fault probabilities of individual links, keyed by `(from, to)`.
+/// - `synchrony`: The partial synchrony model, if the network is not always synchronous.
+/// - `stats`: The counts of messages carried and affected by faults.
 pub struct Network {
     // The sender channels map.
     senders: Arc<Mutex<HashMap<usize, Sender<Message>>>>,
     // The receiver channels map.
     receivers: Arc<Mutex<HashMap<usize, Receiver<Message>>>>,
     pub event_queue: EventQueue,
+    delay_model: Box<dyn DelayModel>,
+    partitions: Vec<Partition>,
+    faults: LinkFaults,
+    link_faults: HashMap<(usize, usize), LinkFaults>,
+    synchrony: Option<PartialSynchrony>,
+    stats: NetworkStats,
 }
 
 impl Default for Network {
@@ -47,9 +88,73 @@ impl Network {
             senders: Arc::new(Mutex::new(HashMap::new())),
             receivers: Arc::new(Mutex::new(HashMap::new())),
             event_queue: EventQueue::new(),
+            delay_model: Box::new(ConstantDelay(1)),
+            partitions: Vec::new(),
+            faults: LinkFaults::default(),
+            link_faults: HashMap::new(),
+            synchrony: None,
+            stats: NetworkStats::default(),
         }
     }
 
+    /// Replaces the delay model, which defaults to a constant delay of 1.
+    /// 
+    /// ## Parameters
+    /// - `model`: The model deciding the delay of each message sent from now on.
+    pub fn set_delay_model(&mut self, model: impl DelayModel + 'static) {
+        self.delay_model = Box::new(model);
+    }
+
+    /// Schedules a partition.
+    /// 
+    /// Messages sent across its boundary while it is active are dropped or held until it
+    /// heals, as configured by the partition.
+    /// 
+    /// ## Parameters
+    /// - `partition`: The partition to schedule.
+    pub fn add_partition(&mut self, partition: Partition) {
+        self.partitions.push(partition);
+    }
+
+    /// Sets the fault probabilities of every link without its own.
+    /// 
+    /// ## Parameters
+    /// - `faults`: The fault probabilities, which default to none.
+    pub fn set_faults(&mut self, faults: LinkFaults) {
+        self.faults = faults;
+    }
+
+    /// Sets the fault probabilities of the link from one node to another.
+    /// 
+    /// ## Parameters
+    /// - `from`: The ID of the sender node.
+    /// - `to`: The ID of the target node.
+    /// - `faults`: The fault probabilities of the link.
+    pub fn set_link_faults(&mut self, from: usize, to: usize, faults: LinkFaults) {
+        self.link_faults.insert((from, to), faults);
+    }
+
+    /// Makes the network partially synchronous.
+    /// 
+    /// Messages sent with `send` before GST are then scheduled by the model's adversary, and
+    /// every message arrives within `Δ` of GST or of being sent, whichever is later.
+    /// 
+    /// ## Parameters
+    /// - `synchrony`: The partial synchrony model.
+    pub fn set_partial_synchrony(&mut self, synchrony: PartialSynchrony) {
+        self.synchrony = Some(synchrony);
+    }
+
+    /// Returns the partial synchrony model, if the network is partially synchronous.
+    pub fn synchrony(&self) -> Option<&PartialSynchrony> {
+        self.synchrony.as_ref()
+    }
+
+    /// Returns the counts of messages carried and affected by faults.
+    pub fn stats(&self) -> NetworkStats {
+        self.stats
+    }
+
     /// Adds a new node to the network and assigns it a unique ID.
     /// 
     /// ## Returns
@@ -66,29 +171,104 @@ impl Network {
         id
     }
 
+    /// Sends a message from one node to another, with a delay drawn from the delay model.
+    /// 
+    /// The link's faults then decide whether the message is lost, delivered twice, or
+    /// given extra jitter. Under partial synchrony, the model finally bounds the delay; a
+    /// message sent before GST is scheduled by the adversary alone, so it gets no jitter.
+    /// 
+    /// ## Parameters
+    /// - `sender_id`: The ID of the sender node.
+    /// - `target_id`: The ID of the target node.
+    /// - `message`: The message to send.
+    /// - `rng`: The simulation's random number generator, used by the delay model, faults and adversary.
+    pub fn send(&mut self, sender_id: usize, target_id: usize, message: Message, rng: &mut StdRng) {
+        let faults = self.link_faults.get(&(sender_id, target_id)).copied().unwrap_or(self.faults);
+        if faults.drops(rng) {
+            self.stats.lost += 1;
+            return;
+        }
+
+        let copies = if faults.duplicates(rng) {
+            self.stats.duplicated += 1;
+            2
+        } else {
+            1
+        };
+        for _ in 0..copies {
+            let mut delay = self.delay_model.delay(sender_id, target_id, rng);
+            let scheduled = self.synchrony.as_ref().is_some_and(|synchrony| synchrony.before_gst(message.timestamp));
+            if scheduled {
+                self.stats.adversary_scheduled += 1;
+            } else if let Some(jitter) = faults.jitter(rng) {
+                self.stats.reordered += 1;
+                delay += jitter;
+            }
+            if let Some(synchrony) = &mut self.synchrony {
+                delay = synchrony.delay(sender_id, target_id, message.timestamp, delay, rng);
+            }
+            self.send_with_delay(sender_id, target_id, message.clone(), delay);
+        }
+    }
+
     /// Sends a message from one node to another with a specified delay.
     /// 
-    /// The message is scheduled in the event queue with the specified delay.
+    /// The message is sent at its timestamp, and scheduled in the event queue for delivery
+    /// after the specified delay. A message crossing the boundary of a partition active when
+    /// it is sent is dropped, or held and sent when the partition heals. Under partial
+    /// synchrony, a message that is delivered still arrives by its deadline, however long a
+    /// partition would hold it.
     /// 
     /// ## Parameters
     /// - `sender_id`: The ID of the sender node.
     /// - `target_id`: The ID of the target node.
     /// - `message`: The message to send.
     /// - `delay`: The delay (in logical time units) before the message is delivered.
-    pub fn send(&mut self, sender_id: usize, target_id: usize, mut message: Message, delay: u64) {
+    pub fn send_with_delay(&mut self, sender_id: usize, target_id: usize, mut message: Message, delay: u64) {
         let senders = self.senders.lock().unwrap();
         if senders.get(&sender_id).is_some() {
             let receivers = self.receivers.lock().unwrap();
             if receivers.get(&target_id).is_some() {
-                // Assign a logical timestamp to the message.
-                message.timestamp += delay;
+                message.sender = sender_id;
+                self.stats.sent += 1;
+
+                // Apply the partitions separating the nodes at the time the message is sent.
+                let mut sent_at = message.timestamp;
+                let mut held = false;
+                while let Some(partition) = self
+                    .partitions
+                    .iter()
+                    .find(|partition| partition.is_active(sent_at) && partition.separates(sender_id, target_id))
+                {
+                    match partition.held {
+                        HeldMessages::Drop => {
+                            self.stats.partition_dropped += 1;
+                            return;
+                        }
+                        HeldMessages::DeliverOnHeal => {
+                            held = true;
+                            sent_at = partition.heal;
+                        }
+                    }
+                }
+                if held {
+                    self.stats.partition_held += 1;
+                }
+
+                // Stamp the delivery time on the message, within the synchrony bound if any.
+                let mut delivery = sent_at + delay;
+                if let Some(synchrony) = &self.synchrony {
+                    delivery = delivery.min(synchrony.deadline(message.timestamp));
+                }
+                message.timestamp = delivery;
 
                 // Schedule the message in the event queue.
                 let event = Event {
                     timestamp: message.timestamp,
                     sender: sender_id,
                     receiver: target_id,
-                    message,
+                    kind: EventKind::Deliver(message),
+                    seq: 0,
                 };
 
                 self.event_queue.schedule(event);
@@ -100,23 +280,68 @@ impl Network {
         }
     }
 
+    /// Schedules a timer that fires on a node at a given time.
+    /// 
+    /// ## Parameters
+    /// - `node_id`: The ID of the node setting the timer.
+    /// - `at`: The simulated time at which the timer fires.
+    /// - `id`: The ID of the timer, used to cancel it.
+    /// - `tag`: The tag handed back to the node when the timer fires.
+    pub fn schedule_timer(&mut self, node_id: usize, at: u64, id: TimerId, tag: u64) {
+        self.event_queue.schedule(Event {
+            timestamp: at,
+            sender: node_id,
+            receiver: node_id,
+            kind: EventKind::Timer { id, tag },
+            seq: 0,
+        });
+    }
+
+    /// Schedules a wake-up of a node at a given time.
+    /// 
+    /// ## Parameters
+    /// - `node_id`: The ID of the node to wake up.
+    /// - `at`: The simulated time at which the node wakes up.
+    pub fn schedule_wake_up(&mut self, node_id: usize, at: u64) {
+        self.event_queue.schedule(Event {
+            timestamp: at,
+            sender: node_id,
+            receiver: node_id,
+            kind: EventKind::WakeUp,
+            seq: 0,
+        });
+    }
+
     /// Processes all scheduled events in the event queue.
     /// 
     /// Messages are delivered to their respective receivers based on their timestamps.
+    /// Timers and wake-ups have no channel to go to and are dropped.
     pub fn process_events(&mut self) {
         while let Some(event) = self.event_queue.process_next_event() {
-            let senders = self.senders.lock().unwrap();
-            if let Some(tx) = senders.get(&event.receiver) {
-                tx.send(event.message).unwrap();
-            } else {
-                error!("Error: Receiver node {} does not exist.", event.receiver);
-            }
+            self.deliver(event);
+        }
+    }
+
+    /// Delivers the message of an event to its receiver's channel, to be read with `receive`.
+    /// 
+    /// ## Parameters
+    /// - `event`: The event to deliver. Events other than deliveries are dropped.
+    pub fn deliver(&self, event: Event) {
+        let EventKind::Deliver(message) = event.kind else {
+            return;
+        };
+        let senders = self.senders.lock().unwrap();
+        if let Some(tx) = senders.get(&event.receiver) {
+            tx.send(message).unwrap();
+        } else {
+            error!("Error: Receiver node {} does not exist.", event.receiver);
         }
     }
 
-    /// Receives a message for the given node.
+    /// Receives a delivered message for the given node.
     /// 
-    /// This method blocks until a message is available for the specified node.
+    /// This method does not block: the simulation is single-threaded, so a message that has
+    /// not been delivered yet could never arrive while waiting.
     /// 
     /// ## Parameters
     /// - `node_id`: The ID of the node receiving the message.
@@ -126,7 +351,7 @@ impl Network {
     pub fn receive(&self, node_id: usize) -> Option<Message> {
         let receivers = self.receivers.lock().unwrap();
         if let Some(rx) = receivers.get(&node_id) {
-            rx.recv().ok()
+            rx.try_recv().ok()
         } else {
             None
         }
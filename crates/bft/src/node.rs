@@ -7,6 +7,7 @@
 use futures::future::{ready};
 use std::pin::Pin;
 use std::collections::HashMap;
+use crate::context::Context;
 use crate::message::Message;
 use futures::FutureExt;
 use std::collections::VecDeque;
@@ -21,16 +22,19 @@ use futures::{future::BoxFuture};
 /// - `propose`: Proposes a value for consensus.
 /// - `vote`: Votes on a proposed value.
 /// - `finalize`: Finalizes a value.
+/// - `on_wake_up`: Reacts to a scheduled wake-up.
+/// - `on_timer`: Reacts to a timer set through the node's context.
 pub trait Node {
     /// Handles an incoming message.
     /// 
     /// ## Parameters
+    /// - `ctx`: The context through which the node sends messages and sets timers.
     /// - `sender`: The ID of the sender node.
     /// - `message`: The message to handle.
     /// 
     /// ## Returns
     /// A future that resolves when the message is processed.
-    fn handle(&mut self, sender: usize, message: Message) -> BoxFuture<'static, ()>;
+    fn handle(&mut self, ctx: &mut Context, sender: usize, message: Message) -> BoxFuture<'static, ()>;
 
     /// Runs the node's main loop.
     /// 
@@ -65,6 +69,29 @@ pub trait Node {
     /// ## Returns
     /// A future that resolves with the finalized value, or `None` if finalization fails.
     fn finalize(&mut self, value: String) -> BoxFuture<'static, Option<String>>;
+
+    /// Handles a wake-up scheduled for the node, e.g. to start running a protocol.
+    /// 
+    /// ## Parameters
+    /// - `ctx`: The context through which the node sends messages and sets timers.
+    /// 
+    /// ## Returns
+    /// A future that resolves when the node has reacted to the wake-up.
+    fn on_wake_up(&mut self, _ctx: &mut Context) -> BoxFuture<'static, ()> {
+        async {}.boxed()
+    }
+
+    /// Handles a timer set through the node's context.
+    /// 
+    /// ## Parameters
+    /// - `ctx`: The context through which the node sends messages and sets timers.
+    /// - `tag`: The tag given when the timer was set.
+    /// 
+    /// ## Returns
+    /// A future that resolves when the node has reacted to the timer.
+    fn on_timer(&mut self, _ctx: &mut Context, _tag: u64) -> BoxFuture<'static, ()> {
+        async {}.boxed()
+    }
 }
 
 /// Represents a passive node in the BFT simulation.
@@ -88,13 +115,13 @@ impl PassiveNode {
 }
 
 impl Node for PassiveNode {
-    fn handle(&mut self, _sender: usize, message: Message) -> BoxFuture<'static, ()> {
+    fn handle(&mut self, _ctx: &mut Context, _sender: usize, message: Message) -> BoxFuture<'static, ()> {
         // Copy the id so that nothing with a short lifetime is captured.
         let id = self.id;
         Box::pin(async move {
             logging::log_info(&format!(
                 "Node {} received message: {}",
-                id, message.content
+                id, message.payload
             ));
         })
     }
@@ -103,9 +130,9 @@ impl Node for PassiveNode {
         // For a passive node, we simply return an empty iterator.
         Box::new(std::iter::empty())
     }
-fn propose(&mut self, _: String) -> Pin<Box<(dyn futures::Future<Output = ()> + std::marker::Send + 'static)>> { todo!() }
-fn vote(&mut self, _: usize, _: String) -> Pin<Box<(dyn futures::Future<Output = ()> + std::marker::Send + 'static)>> { todo!() }
-fn finalize(&mut self, _: String) -> Pin<Box<(dyn futures::Future<Output = Option<String>> + std::marker::Send + 'static)>> { todo!() }
+fn propose(&mut self, _: String) -> Pin<Box<dyn futures::Future<Output = ()> + std::marker::Send + 'static>> { todo!() }
+fn vote(&mut self, _: usize, _: String) -> Pin<Box<dyn futures::Future<Output = ()> + std::marker::Send + 'static>> { todo!() }
+fn finalize(&mut self, _: String) -> Pin<Box<dyn futures::Future<Output = Option<String>> + std::marker::Send + 'static>> { todo!() }
 }
 
 /// Represents a sequential node in the BFT simulation.
@@ -135,7 +162,7 @@ impl SequentialNode {
 impl Node for SequentialNode {
     // When a message is received, push it into the mailbox.
     // We immediately return a future that resolves to ().
-    fn handle(&mut self, sender: usize, message: Message) -> BoxFuture<'static, ()> {
+    fn handle(&mut self, _ctx: &mut Context, sender: usize, message: Message) -> BoxFuture<'static, ()> {
         self.mailbox.push_back((sender, message));
         async {}.boxed() // Return an immediately-ready future.
     }
@@ -153,7 +180,7 @@ impl Node for SequentialNode {
                     // Replace `logging::log_info` with your own logging function.
                     logging::log_info(&format!(
                         "Node {} handling message from {}: {}",
-                        id, sender, message.content
+                        id, sender, message.payload
                     ));
                 }
                 .boxed();
@@ -163,9 +190,9 @@ impl Node for SequentialNode {
             }
         }))
     }
-fn propose(&mut self, _: String) -> Pin<Box<(dyn futures::Future<Output = ()> + std::marker::Send + 'static)>> { todo!() }
-fn vote(&mut self, _: usize, _: String) -> Pin<Box<(dyn futures::Future<Output = ()> + std::marker::Send + 'static)>> { todo!() }
-fn finalize(&mut self, _: String) -> Pin<Box<(dyn futures::Future<Output = Option<String>> + std::marker::Send + 'static)>> { todo!() }
+fn propose(&mut self, _: String) -> Pin<Box<dyn futures::Future<Output = ()> + std::marker::Send + 'static>> { todo!() }
+fn vote(&mut self, _: usize, _: String) -> Pin<Box<dyn futures::Future<Output = ()> + std::marker::Send + 'static>> { todo!() }
+fn finalize(&mut self, _: String) -> Pin<Box<dyn futures::Future<Output = Option<String>> + std::marker::Send + 'static>> { todo!() }
 }
 
 /// Represents an honest node in the BFT simulation.
@@ -219,7 +246,7 @@ impl HonestNode {
 }
 
 impl Node for HonestNode {
-    fn handle(&mut self, sender: usize, message: Message) -> BoxFuture<'static, ()> {
+    fn handle(&mut self, _ctx: &mut Context, sender: usize, message: Message) -> BoxFuture<'static, ()> {
         // Update the logical clock
         self.update_clock(message.timestamp);
 
@@ -235,7 +262,7 @@ impl Node for HonestNode {
                 let future = async move {
                     logging::log_info(&format!(
                         "Node {} handling message from {}: {}",
-                        id, sender, message.content
+                        id, sender, message.payload
                     ));
                 }
                 .boxed();
@@ -291,7 +318,7 @@ impl ByzantineNode {
 }
 
 impl Node for ByzantineNode {
-    fn handle(&mut self, _sender: usize, _message: Message) -> BoxFuture<'static, ()> {
+    fn handle(&mut self, _ctx: &mut Context, _sender: usize, _message: Message) -> BoxFuture<'static, ()> {
         // Byzantine nodes may ignore messages or send conflicting responses
         async {}.boxed()
     }
@@ -0,0 +1,67 @@
+//! # Partition Module
+//!
+//! This module describes network partitions for testing safety and liveness across splits.
+//!
+//! A `Partition` splits the nodes into groups from a start time until it heals. A message
+//! sent between different groups while the partition is active is either dropped, or held
+//! and released when the partition heals, after which it still takes its usual delay.
+
+/// What happens to messages crossing a partition boundary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeldMessages {
+    /// Messages crossing the boundary are lost.
+    Drop,
+    /// Messages crossing the boundary are held and sent when the partition heals.
+    DeliverOnHeal,
+}
+
+/// A network partition active during `[start, heal)`.
+///
+/// ## Fields
+/// - `groups`: The groups of node IDs that can reach each other. Nodes not listed in any
+///   group form one more group together.
+/// - `start`: The simulated time at which the partition starts.
+/// - `heal`: The simulated time at which the partition heals.
+/// - `held`: What happens to messages crossing the boundary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Partition {
+    pub groups: Vec<Vec<usize>>,
+    pub start: u64,
+    pub heal: u64,
+    pub held: HeldMessages,
+}
+
+impl Partition {
+    /// Creates a partition.
+    ///
+    /// ## Parameters
+    /// - `groups`: The groups of node IDs that can reach each other.
+    /// - `start`: The simulated time at which the partition starts.
+    /// - `heal`: The simulated time at which the partition heals.
+    /// - `held`: What happens to messages crossing the boundary.
+    ///
+    /// ## Returns
+    /// A new `Partition` instance.
+    pub fn new(groups: Vec<Vec<usize>>, start: u64, heal: u64, held: HeldMessages) -> Self {
+        assert!(start <= heal, "partition heals before it starts");
+        Self { groups, start, heal, held }
+    }
+
+    /// Checks if the partition is active at a given time.
+    pub fn is_active(&self, time: u64) -> bool {
+        self.start <= time && time < self.heal
+    }
+
+    /// Returns the index of the group of a node, with unlisted nodes in the last group.
+    fn group_of(&self, node: usize) -> usize {
+        self.groups
+            .iter()
+            .position(|group| group.contains(&node))
+            .unwrap_or(self.groups.len())
+    }
+
+    /// Checks if the partition separates two nodes while it is active.
+    pub fn separates(&self, a: usize, b: usize) -> bool {
+        self.group_of(a) != self.group_of(b)
+    }
+}
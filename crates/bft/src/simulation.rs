@@ -3,14 +3,23 @@
 //! This module implements the simulation framework for the BFT protocol.
 //!
 //! The `Simulation` struct manages the network and nodes, facilitating the execution of the BFT protocol.
-//! It processes events from the event queue and runs the main loop for each node.
+//! It owns the global simulated clock, and dispatches events from the event queue to their
+//! receivers one at a time, in timestamp order.
 
 use crate::logging;
+use crate::clock::Clock;
+use crate::context::Context;
+use crate::event_queue::{EventKind, TimerId};
 use crate::message::Message;
 use crate::node::Node;
 use crate::network::Network;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use std::sync::{Arc, Mutex};
 
+/// The seed used by `Simulation::new`.
+pub const DEFAULT_SEED: u64 = 0;
+
 /// Represents the simulation framework for the BFT protocol.
 /// 
 /// The `Simulation` struct manages the network and nodes, processing events and running the main loop for each node.
@@ -18,10 +27,16 @@ use std::sync::{Arc, Mutex};
 /// ## Fields
 /// - `network`: The communication network for the simulation.
 /// - `nodes`: The list of nodes participating in the simulation.
+/// - `clock`: The global simulated clock.
+/// - `next_timer_id`: The ID given to the next timer a node sets.
+/// - `rng`: The seeded random number generator behind every random choice in the run.
 pub struct Simulation {
     // Wrap Network in a Mutex to allow mutable access behind the Arc.
     pub network: Arc<Mutex<Network>>,
     pub nodes: Vec<Arc<Mutex<dyn Node + Send + Sync>>>,
+    clock: Clock,
+    next_timer_id: TimerId,
+    rng: StdRng,
 }
 
 impl Default for Simulation {
@@ -31,19 +46,45 @@ impl Default for Simulation {
 }
 
 impl Simulation {
-    /// Creates a new simulation framework.
+    /// Creates a new simulation framework seeded with `DEFAULT_SEED`.
     /// 
-    /// Initializes an empty network and an empty list of nodes.
+    /// Initializes an empty network, an empty list of nodes and a clock at time 0.
     /// 
     /// ## Returns
     /// A new `Simulation` instance.
     pub fn new() -> Self {
+        Self::with_seed(DEFAULT_SEED)
+    }
+
+    /// Creates a new simulation framework whose random choices are drawn from the given seed.
+    /// 
+    /// Two runs with the same seed, nodes and inputs process the same events in the same order.
+    /// 
+    /// ## Parameters
+    /// - `seed`: The seed of the simulation's random number generator.
+    /// 
+    /// ## Returns
+    /// A new `Simulation` instance.
+    pub fn with_seed(seed: u64) -> Self {
         Self {
             network: Arc::new(Mutex::new(Network::new())),
             nodes: Vec::new(),
+            clock: Clock::new(),
+            next_timer_id: 0,
+            rng: StdRng::seed_from_u64(seed),
         }
     }
 
+    /// Returns the random number generator for random choices made outside of nodes.
+    pub fn rng(&mut self) -> &mut StdRng {
+        &mut self.rng
+    }
+
+    /// Returns the current simulated time.
+    pub fn now(&self) -> u64 {
+        self.clock.now()
+    }
+
     /// Adds a new node to the simulation.
     /// 
     /// ## Parameters
@@ -56,60 +97,173 @@ impl Simulation {
         };
 
         let node_arc = Arc::new(Mutex::new(node));
+        self.nodes.push(node_arc.clone());
 
+        // Initialize the node.
+        let mut ctx = self.context(node_id);
         {
-            // Initialize the node.
             let mut node_lock = node_arc.lock().unwrap();
             std::mem::drop(node_lock.handle(
-                0,
-                Message {
-                    content: format!("Node {} initialized.", node_id),
-                    timestamp: 0,
-                },
+                &mut ctx,
+                node_id,
+                Message::text(node_id, format!("Node {} initialized.", node_id)),
             ));
         }
+        self.route(ctx);
+    }
+
+    /// Schedules a wake-up of a node, e.g. to start running its protocol.
+    /// 
+    /// ## Parameters
+    /// - `node_id`: The ID of the node to wake up.
+    /// - `at`: The simulated time at which the node wakes up.
+    pub fn wake_up(&mut self, node_id: usize, at: u64) {
+        self.network.lock().unwrap().schedule_wake_up(node_id, at);
+    }
 
-        self.nodes.push(node_arc);
+    /// Creates a context for a node at the current time, with a generator seeded from the simulation's.
+    fn context(&mut self, node_id: usize) -> Context {
+        let rng = StdRng::seed_from_u64(self.rng.gen());
+        Context::with_state(node_id, self.clock.now(), self.nodes.len(), self.next_timer_id, rng)
+    }
+
+    /// Routes the messages sent and timers set or cancelled through a node's context.
+    /// 
+    /// Messages go through the network with delays drawn from its delay model, and timers are scheduled in the
+    /// event queue relative to the current time.
+    fn route(&mut self, mut ctx: Context) {
+        self.next_timer_id = ctx.next_timer_id();
+        let now = self.clock.now();
+        let mut network = self.network.lock().unwrap();
+        for (target, message) in ctx.take_sent() {
+            network.send(ctx.id(), target, message, &mut self.rng);
+        }
+        for timer in ctx.take_timers() {
+            network.schedule_timer(ctx.id(), now + timer.delay, timer.id, timer.tag);
+        }
+        for id in ctx.take_cancelled() {
+            network.event_queue.cancel_timer(id);
+        }
     }
 
     /// Starts the simulation.
     /// 
-    /// Processes events from the event queue and runs the main loop for each node.
-    /// The simulation continues until the event queue is empty.
+    /// Runs until the event queue is empty. Protocols that keep setting timers never empty
+    /// it, and should be run with `run_until` instead.
     pub async fn start(&mut self) {
+        self.run_until(u64::MAX).await;
+    }
+
+    /// Runs the simulation up to a given time.
+    /// 
+    /// Pops events from the event queue in timestamp order, advancing the clock to each.
+    /// A delivered message goes to its receiver's `handle`, a timer to its `on_timer` and a
+    /// wake-up to its `on_wake_up`, after which the receiver's main loop runs. Whatever the
+    /// receiver sends or schedules through its context is routed before the next event is
+    /// processed. Messages addressed to network nodes outside the simulation are delivered to
+    /// their channels instead.
+    /// 
+    /// ## Parameters
+    /// - `deadline`: The time of the last events to process; later events stay queued.
+    pub async fn run_until(&mut self, deadline: u64) {
         logging::log_info("Starting BFT simulation...");
 
-        // Continue processing until the event queue is empty.
         loop {
-            {
-                // Check if the event queue is empty.
-                let network = self.network.lock().unwrap();
-                if network.event_queue.is_empty() {
-                    break;
+            // Pop the next event, releasing the network before the node runs.
+            let event = {
+                let mut network = self.network.lock().unwrap();
+                match network.event_queue.next_timestamp() {
+                    Some(timestamp) if timestamp <= deadline => network.event_queue.process_next_event(),
+                    _ => None,
                 }
-            }
+            };
+            let Some(event) = event else {
+                break;
+            };
 
-            // Lock the network mutably to process events.
-            {
-                let mut network = self.network.lock().unwrap();
-                network.process_events();
-            }
+            let Some(node) = self.nodes.get(event.receiver).cloned() else {
+                self.network.lock().unwrap().deliver(event);
+                continue;
+            };
+            self.clock.advance_to(event.timestamp);
 
-            // Run each node's main loop.
-            for node in &self.nodes {
-                // Collect the async effects produced by the node's run.
-                let effects: Vec<_> = {
-                    let mut node_lock = node.lock().unwrap();
-                    node_lock.run().collect()
-                };
-
-                // Await each effect.
-                for effect in effects {
-                    effect.await;
+            let mut ctx = self.context(event.receiver);
+            let effect = {
+                let mut node_lock = node.lock().unwrap();
+                match event.kind {
+                    EventKind::Deliver(message) => node_lock.handle(&mut ctx, event.sender, message),
+                    EventKind::Timer { tag, .. } => node_lock.on_timer(&mut ctx, tag),
+                    EventKind::WakeUp => node_lock.on_wake_up(&mut ctx),
                 }
+            };
+            effect.await;
+
+            // Collect the async effects produced by the receiver's run, and await each.
+            let effects: Vec<_> = {
+                let mut node_lock = node.lock().unwrap();
+                node_lock.run().collect()
+            };
+            for effect in effects {
+                effect.await;
             }
+
+            self.route(ctx);
         }
 
         logging::log_info("BFT simulation completed.");
     }
+
+    /// Returns the last epoch a node reports as final, among epochs `0..=max_epoch`.
+    ///
+    /// Nodes are asked through `finalize`, with the epoch as the value.
+    pub async fn last_final_epoch(&self, node_id: usize, max_epoch: usize) -> Option<usize> {
+        let mut last_final = None;
+        for epoch in 0..=max_epoch {
+            let future = self.nodes[node_id].lock().unwrap().finalize(epoch.to_string());
+            if future.await.is_some() {
+                last_final = Some(epoch);
+            }
+        }
+        last_final
+    }
+
+    /// Checks that liveness resumes after GST.
+    ///
+    /// Runs the simulation up to GST, and then up to `bound` after it, asserting that the
+    /// last final epoch of every honest node advanced in between. The simulation must not
+    /// have run past GST yet.
+    ///
+    /// ## Parameters
+    /// - `honest`: The IDs of the honest nodes.
+    /// - `bound`: The time after GST by which every honest node must finalize a later epoch.
+    /// - `max_epoch`: The last epoch to ask the nodes about.
+    ///
+    /// ## Panics
+    /// If the network is not partially synchronous, or an honest node's last final epoch
+    /// did not advance within the bound.
+    pub async fn assert_live_after_gst(&mut self, honest: &[usize], bound: u64, max_epoch: usize) {
+        let gst = {
+            let network = self.network.lock().unwrap();
+            network.synchrony().expect("the network is not partially synchronous").gst
+        };
+        assert!(self.now() <= gst, "the simulation already ran past GST");
+
+        self.run_until(gst).await;
+        let mut at_gst = Vec::new();
+        for &id in honest {
+            at_gst.push(self.last_final_epoch(id, max_epoch).await);
+        }
+
+        self.run_until(gst.saturating_add(bound)).await;
+        for (&id, before) in honest.iter().zip(at_gst) {
+            let after = self.last_final_epoch(id, max_epoch).await;
+            assert!(
+                after > before,
+                "node {} is stuck at final epoch {:?} {} after GST",
+                id,
+                after,
+                bound
+            );
+        }
+    }
 }
@@ -13,6 +13,14 @@
 //! - **Proposal and Block Creation**: Proposals are created, signed, and finalized into blocks.
 //! - **Notarization**: Proposals are notarized when they receive enough signatures.
 //! - **Finalization**: Finalized blocks are added to the chain.
+//! - **Node**: [`StreamletNode`] runs the protocol on the simulation.
+
+use std::collections::HashSet;
+use crate::*; // Import everything from the parent module (`bft/src/lib.rs`)
+
+pub mod node;
+
+pub use node::{StreamletNode, EPOCH_LENGTH};
 
 /// Represents the genesis block in the Streamlet protocol.
 /// 
@@ -21,10 +29,6 @@
 /// ## Fields
 /// - `n`: The total number of nodes in the network.
 /// - `t`: The maximum number of faulty nodes tolerated by the protocol.
-
-use std::collections::HashSet;
-use crate::*; // Import everything from the parent module (`bft/src/lib.rs`)
-
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct StreamletGenesis {
     pub n: usize,
@@ -34,7 +38,7 @@ pub struct StreamletGenesis {
 impl StreamletGenesis {
     /// Creates a new Streamlet genesis block.
     /// 
-    /// The threshold `t` is calculated as `(n * 2 + 2) / 3`.
+    /// The threshold `t` is calculated as `ceil(2n / 3)`.
     /// 
     /// ## Parameters
     /// - `n`: The total number of nodes in the network.
@@ -42,7 +46,7 @@ impl StreamletGenesis {
     /// ## Returns
     /// A new `StreamletGenesis` instance.
     pub fn new(n: usize) -> Self {
-        let t = (n * 2 + 2) / 3;
+        let t = (n * 2).div_ceil(3);
         Self { n, t }
     }
 
@@ -135,13 +139,12 @@ impl StreamletProposal {
 
     /// Checks if the proposal is notarized.
     /// 
-    /// A proposal is notarized when it receives enough signatures (`t + 1`).
+    /// A proposal is notarized when it receives enough signatures (`t`).
     /// 
     /// ## Returns
     /// `true` if the proposal is notarized, `false` otherwise.
     pub fn is_notarized(&self) -> bool {
-        let required_signatures = self.t() + 1; // t + 1 signatures required for notarization
-        self.signatures.len() >= required_signatures
+        self.signatures.len() >= self.t()
     }
 
     /// Asserts that the proposal is notarized.
@@ -213,12 +216,9 @@ impl StreamletBlock {
                 ref other => return other.clone(),
             };
 
+            last = middle;
+            middle = first;
             first = new_first;
-            // Update middle and last:
-            let new_middle = first; // previous `first`
-            let new_last = middle;  // previous `middle`
-            middle = new_middle;
-            last = new_last;
         }
     }
 
@@ -0,0 +1,310 @@
+//! # Streamlet Node
+//!
+//! This module implements a node running the Streamlet protocol on the simulation.
+//!
+//! Time is divided into epochs of `epoch_length` simulated time units, each with a single leader.
+//! A node enters epoch 1 when it is woken up, and every later epoch when the timer it set on
+//! entering the previous one fires. In every epoch:
+//! - the leader proposes a block extending one of the longest notarized chains it has seen;
+//! - every node votes for the first valid proposal it receives from the leader of the epoch;
+//! - a block is notarized once `t` votes for it are seen, whether or not this node saw its
+//!   proposal; votes for a block whose parent is not notarized yet wait for the parent;
+//! - every node relays each vote of another node the first time it sees it, so a vote that
+//!   reaches one node eventually reaches all, even over lossy links; votes are signed, so
+//!   relaying cannot forge or alter them;
+//! - when a chain contains notarized blocks from three consecutive epochs, the middle block
+//!   and its prefix are final.
+//!
+//! Proposals and votes are broadcast through the node's `Context` as typed messages naming the epoch
+//! and the parent block, with votes signed by their voter. Blocks are identified by their epoch, since each epoch has at most one
+//! notarized block. A block's proposal refers to its parent's proposal rather than the parent
+//! block, so that each block holds a single copy of its chain of blocks.
+
+use std::collections::{HashMap, HashSet};
+use futures::future::{ready, BoxFuture};
+use futures::FutureExt;
+use crate::context::Context;
+use crate::logging;
+use crate::message::{Message, Payload, SignedVote};
+use crate::node::Node;
+use crate::PermissionedBFTEnum;
+use super::{StreamletBlock, StreamletGenesis, StreamletProposal};
+
+/// The default length of an epoch, in simulated time units.
+pub const EPOCH_LENGTH: u64 = 10;
+
+/// A node running the Streamlet protocol.
+///
+/// ## Fields
+/// - `id`: The unique ID of the node, which must match its ID in the network.
+/// - `genesis`: The genesis block, fixing `n` and `t`.
+/// - `epoch`: The current epoch.
+/// - `epoch_length`: The length of an epoch, in simulated time units.
+/// - `proposals`: The accepted proposal of each epoch, not yet notarized.
+/// - `notarized`: The notarized blocks, keyed by epoch.
+/// - `heights`: The chain length of each notarized block, keyed by epoch.
+/// - `votes`: The voters seen for each `(epoch, parent epoch)` pair.
+/// - `voted`: The epochs in which this node has voted.
+/// - `last_final`: The last final block.
+pub struct StreamletNode {
+    pub id: usize,
+    pub genesis: StreamletGenesis,
+    pub epoch: usize,
+    pub epoch_length: u64,
+    pub proposals: HashMap<usize, StreamletProposal>,
+    pub notarized: HashMap<usize, StreamletBlock>,
+    pub heights: HashMap<usize, usize>,
+    pub votes: HashMap<(usize, usize), HashSet<usize>>,
+    pub voted: HashSet<usize>,
+    pub last_final: PermissionedBFTEnum,
+}
+
+impl StreamletNode {
+    /// Creates a new Streamlet node.
+    ///
+    /// ## Parameters
+    /// - `id`: The unique ID of the node.
+    /// - `n`: The total number of nodes.
+    ///
+    /// ## Returns
+    /// A new `StreamletNode` instance with epochs of `EPOCH_LENGTH`, whose only notarized
+    /// block is the genesis block.
+    pub fn new(id: usize, n: usize) -> Self {
+        let genesis = StreamletGenesis::new(n);
+        let last_final = genesis.last_final();
+        Self {
+            id,
+            genesis,
+            epoch: 0,
+            epoch_length: EPOCH_LENGTH,
+            proposals: HashMap::new(),
+            notarized: HashMap::new(),
+            heights: HashMap::from([(0, 0)]),
+            votes: HashMap::new(),
+            voted: HashSet::new(),
+            last_final,
+        }
+    }
+
+    /// Returns the node with epochs of the given length.
+    pub fn with_epoch_length(mut self, epoch_length: u64) -> Self {
+        self.epoch_length = epoch_length;
+        self
+    }
+
+    /// Returns the leader of an epoch.
+    pub fn leader(&self, epoch: usize) -> usize {
+        epoch % self.genesis.n
+    }
+
+    /// Returns the notarized block of an epoch, with epoch 0 being the genesis block.
+    pub fn block(&self, epoch: usize) -> Option<PermissionedBFTEnum> {
+        if epoch == 0 {
+            return Some(self.genesis.last_final());
+        }
+        self.notarized.get(&epoch).cloned().map(PermissionedBFTEnum::Block)
+    }
+
+    /// Returns the epoch of the tip of the longest notarized chain.
+    ///
+    /// Ties between chains of equal length are broken in favour of the latest epoch.
+    pub fn longest_notarized_tip(&self) -> usize {
+        self.heights
+            .iter()
+            .max_by_key(|(epoch, height)| (**height, **epoch))
+            .map_or(0, |(epoch, _)| *epoch)
+    }
+
+    /// Checks if the block of an epoch is final, i.e. it is on the chain of the last final block.
+    pub fn is_final(&self, epoch: usize) -> bool {
+        let mut current = Some(&self.last_final);
+        while let Some(block) = current {
+            if block.epoch() == epoch {
+                return true;
+            }
+            if block.epoch() < epoch {
+                return false;
+            }
+            current = block.parent();
+        }
+        false
+    }
+
+    /// Returns the parent a proposal extending the block of an epoch refers to: the block's
+    /// own proposal, or the genesis block for epoch 0.
+    fn proposal_parent(&self, epoch: usize) -> Option<PermissionedBFTEnum> {
+        if epoch == 0 {
+            return Some(self.genesis.last_final());
+        }
+        self.notarized
+            .get(&epoch)
+            .map(|block| PermissionedBFTEnum::Proposal((*block.proposal).clone()))
+    }
+
+    /// Enters an epoch, setting a timer for the next one and proposing if this node leads it.
+    fn enter_epoch(&mut self, ctx: &mut Context, epoch: usize) {
+        self.epoch = epoch;
+        ctx.set_timer(self.epoch_length, epoch as u64 + 1);
+        self.propose_block(ctx);
+    }
+
+    /// Proposes a block for the current epoch on the longest notarized chain, if this node leads it.
+    fn propose_block(&mut self, ctx: &mut Context) {
+        if self.epoch == 0 || self.leader(self.epoch) != self.id {
+            return;
+        }
+        let parent = self.longest_notarized_tip();
+        logging::log_info(&format!(
+            "Streamlet node {} proposing epoch {} on parent {}",
+            self.id, self.epoch, parent
+        ));
+        ctx.broadcast(Message::new(self.id, self.epoch, Payload::Proposal { parent_epoch: parent }));
+    }
+
+    /// Handles a proposal, accepting and voting for it if it is the first valid one of the epoch.
+    fn on_proposal(&mut self, ctx: &mut Context, sender: usize, epoch: usize, parent: usize) {
+        if sender != self.leader(epoch) || epoch != self.epoch || self.voted.contains(&epoch) {
+            return;
+        }
+        let parent_ref = match self.proposal_parent(parent) {
+            Some(parent_ref) if parent < epoch => parent_ref,
+            _ => return,
+        };
+        // Only vote for proposals extending one of the longest notarized chains.
+        let longest = self.heights[&self.longest_notarized_tip()];
+        if self.heights[&parent] < longest {
+            return;
+        }
+        self.proposals.insert(epoch, StreamletProposal::new(Box::new(parent_ref), epoch));
+        self.cast_vote(ctx, epoch);
+    }
+
+    /// Broadcasts a vote for the accepted proposal of an epoch, at most once per epoch.
+    fn cast_vote(&mut self, ctx: &mut Context, epoch: usize) {
+        let parent = match self.proposals.get(&epoch) {
+            Some(proposal) => proposal.parent.epoch(),
+            None => return,
+        };
+        if self.voted.insert(epoch) {
+            let vote = ctx.sign_vote(epoch, parent);
+            ctx.broadcast(Message::new(self.id, epoch, Payload::Vote(vote)));
+        }
+    }
+
+    /// Records a vote, relays it if it is new, and notarizes the block it is for once it has
+    /// enough votes.
+    ///
+    /// The vote counts for its signer, whoever relayed it.
+    fn on_vote(&mut self, ctx: &mut Context, vote: SignedVote) {
+        let (epoch, parent) = (vote.epoch(), vote.parent_epoch());
+        if !self.votes.entry((epoch, parent)).or_default().insert(vote.voter()) {
+            return;
+        }
+        // This node's own votes were already broadcast by itself.
+        if vote.voter() != self.id {
+            ctx.broadcast(Message::new(self.id, epoch, Payload::Vote(vote)));
+        }
+        self.try_notarize(epoch, parent);
+    }
+
+    /// Notarizes the block of an epoch on a parent if it has `t` votes, and updates finality.
+    ///
+    /// The block is built from the votes alone, so a node notarizes it even if it missed the
+    /// proposal. If the parent is not notarized yet, the votes are kept until it is.
+    fn try_notarize(&mut self, epoch: usize, parent: usize) {
+        if parent >= epoch || self.notarized.contains_key(&epoch) {
+            return;
+        }
+        let Some(voters) = self.votes.get(&(epoch, parent)) else {
+            return;
+        };
+        let (Some(parent_block), Some(parent_ref)) = (self.block(parent), self.proposal_parent(parent)) else {
+            return;
+        };
+        let mut proposal = StreamletProposal::new(Box::new(parent_ref), epoch);
+        proposal.signatures = voters.clone();
+        if !proposal.is_notarized() {
+            return;
+        }
+        self.proposals.remove(&epoch);
+
+        let block = StreamletBlock {
+            parent: Some(Box::new(parent_block)),
+            proposal: Box::new(proposal),
+        };
+        let last_final = block.last_final();
+        self.heights.insert(epoch, self.heights[&parent] + 1);
+        self.notarized.insert(epoch, block);
+        logging::log_info(&format!("Streamlet node {} notarized epoch {}", self.id, epoch));
+
+        if last_final.epoch() > self.last_final.epoch() {
+            logging::log_info(&format!(
+                "Streamlet node {} finalized epoch {}",
+                self.id,
+                last_final.epoch()
+            ));
+            self.last_final = last_final;
+        }
+
+        // Votes for blocks extending this one may have been waiting for it.
+        let children: Vec<usize> = self
+            .votes
+            .keys()
+            .filter(|(_, voted_parent)| *voted_parent == epoch)
+            .map(|(child, _)| *child)
+            .collect();
+        for child in children {
+            self.try_notarize(child, epoch);
+        }
+    }
+}
+
+impl Node for StreamletNode {
+    fn handle(&mut self, ctx: &mut Context, sender: usize, message: Message) -> BoxFuture<'static, ()> {
+        match message.payload {
+            Payload::Proposal { parent_epoch } => self.on_proposal(ctx, sender, message.epoch, parent_epoch),
+            Payload::Vote(vote) => self.on_vote(ctx, vote),
+            other => logging::log_debug(&format!(
+                "Streamlet node {} ignoring message: {}",
+                self.id, other
+            )),
+        }
+        async {}.boxed()
+    }
+
+    fn run(&mut self) -> Box<dyn Iterator<Item = BoxFuture<'static, ()>> + Send + '_> {
+        // All work happens as messages are handled.
+        Box::new(std::iter::empty())
+    }
+
+    fn propose(&mut self, value: String) -> BoxFuture<'static, ()> {
+        // Proposals are broadcast when an epoch starts, where a context is available.
+        logging::log_info(&format!("Streamlet node {} asked to propose: {}", self.id, value));
+        async {}.boxed()
+    }
+
+    fn vote(&mut self, proposal_id: usize, _value: String) -> BoxFuture<'static, ()> {
+        // Votes are broadcast as proposals are handled, where a context is available.
+        logging::log_info(&format!("Streamlet node {} asked to vote for epoch {}", self.id, proposal_id));
+        async {}.boxed()
+    }
+
+    fn finalize(&mut self, value: String) -> BoxFuture<'static, Option<String>> {
+        // `value` names the epoch of a block; it is returned if that block is final.
+        let is_final = value.parse().is_ok_and(|epoch| self.is_final(epoch));
+        ready(is_final.then_some(value)).boxed()
+    }
+
+    fn on_wake_up(&mut self, ctx: &mut Context) -> BoxFuture<'static, ()> {
+        if self.epoch == 0 {
+            self.enter_epoch(ctx, 1);
+        }
+        async {}.boxed()
+    }
+
+    fn on_timer(&mut self, ctx: &mut Context, tag: u64) -> BoxFuture<'static, ()> {
+        // Timers are only set for epochs, tagged with the epoch to enter.
+        self.enter_epoch(ctx, tag as usize);
+        async {}.boxed()
+    }
+}
@@ -0,0 +1,103 @@
+//! # Synchrony Module
+//!
+//! This module implements the partial synchrony model BFT protocols such as Streamlet are
+//! specified under.
+//!
+//! Before the Global Stabilization Time (GST), an `Adversary` schedules messages and may delay
+//! them arbitrarily, but a message sent at time `t` still arrives by `max(t, GST) + Δ`. From GST
+//! on, the network's usual delays apply, capped at `Δ`. Protocols are expected to be safe
+//! throughout and live again after GST.
+//!
+//! Partitions and link faults still apply on top of this model, and may lose messages, but every
+//! message delivered arrives by its deadline, even one a partition holds or a link duplicates.
+//! Link jitter only applies from GST on, since the adversary alone chooses the delay of earlier
+//! messages.
+
+use rand::rngs::StdRng;
+use crate::delay::DelayModel;
+
+/// An adversarial scheduler, choosing the delay of messages sent before GST.
+pub trait Adversary: Send {
+    /// Returns the delay the adversary wants for a message, before the model caps it.
+    ///
+    /// ## Parameters
+    /// - `from`: The ID of the sender node.
+    /// - `to`: The ID of the target node.
+    /// - `sent_at`: The simulated time at which the message was sent.
+    /// - `rng`: The simulation's random number generator.
+    fn delay(&mut self, from: usize, to: usize, sent_at: u64, rng: &mut StdRng) -> u64;
+}
+
+/// Any delay model can serve as an adversary that ignores the time messages are sent.
+impl<D: DelayModel> Adversary for D {
+    fn delay(&mut self, from: usize, to: usize, _sent_at: u64, rng: &mut StdRng) -> u64 {
+        DelayModel::delay(self, from, to, rng)
+    }
+}
+
+/// An adversary holding every message as long as the model allows, i.e. until `GST + Δ`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WorstCaseAdversary;
+
+impl Adversary for WorstCaseAdversary {
+    fn delay(&mut self, _from: usize, _to: usize, _sent_at: u64, _rng: &mut StdRng) -> u64 {
+        u64::MAX
+    }
+}
+
+/// The partial synchrony model.
+///
+/// ## Fields
+/// - `gst`: The Global Stabilization Time.
+/// - `delta`: The bound `Δ` on message delay from GST on.
+/// - `adversary`: The scheduler of messages sent before GST.
+pub struct PartialSynchrony {
+    pub gst: u64,
+    pub delta: u64,
+    adversary: Box<dyn Adversary>,
+}
+
+impl PartialSynchrony {
+    /// Creates a partial synchrony model.
+    ///
+    /// ## Parameters
+    /// - `gst`: The Global Stabilization Time.
+    /// - `delta`: The bound `Δ` on message delay from GST on.
+    /// - `adversary`: The scheduler of messages sent before GST.
+    ///
+    /// ## Returns
+    /// A new `PartialSynchrony` instance.
+    pub fn new(gst: u64, delta: u64, adversary: impl Adversary + 'static) -> Self {
+        Self { gst, delta, adversary: Box::new(adversary) }
+    }
+
+    /// Checks if the network is still asynchronous at a given time.
+    pub fn before_gst(&self, time: u64) -> bool {
+        time < self.gst
+    }
+
+    /// Returns the latest time at which a message sent at `sent_at` may arrive.
+    pub fn deadline(&self, sent_at: u64) -> u64 {
+        sent_at.max(self.gst).saturating_add(self.delta)
+    }
+
+    /// Returns the delay of a message under the model.
+    ///
+    /// Before GST the adversary's delay is used, and from GST on the network's usual delay;
+    /// either way the message arrives by its deadline.
+    ///
+    /// ## Parameters
+    /// - `from`: The ID of the sender node.
+    /// - `to`: The ID of the target node.
+    /// - `sent_at`: The simulated time at which the message was sent.
+    /// - `delay`: The delay the network would otherwise apply.
+    /// - `rng`: The simulation's random number generator, used by the adversary.
+    pub fn delay(&mut self, from: usize, to: usize, sent_at: u64, delay: u64, rng: &mut StdRng) -> u64 {
+        let delay = if self.before_gst(sent_at) {
+            self.adversary.delay(from, to, sent_at, rng)
+        } else {
+            delay
+        };
+        delay.min(self.deadline(sent_at) - sent_at)
+    }
+}
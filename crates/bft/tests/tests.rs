@@ -2,11 +2,19 @@
 mod tests {
     
     use futures::executor::block_on;
+    use futures::future::BoxFuture;
+    use futures::FutureExt;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use bft::delay::{ConstantDelay, DelayModel, ExponentialDelay, LogNormalDelay, MatrixDelay, UniformDelay};
     use std::sync::Mutex;
     use async_std::sync::Arc;
-    use bft::event_queue::Event;
+    use bft::event_queue::{Event, EventKind};
     use bft::event_queue::EventQueue;
-    use bft::network::Network;
+    use bft::network::{Network, NetworkStats};
+    use bft::partition::{HeldMessages, Partition};
+    use bft::faults::LinkFaults;
+    use bft::synchrony::{PartialSynchrony, WorstCaseAdversary};
     
     use util::logging;
     use bft::streamlet::StreamletGenesis;
@@ -15,7 +23,10 @@ mod tests {
     use bft::PermissionedBFTBase;
     use bft::streamlet::StreamletBlock;
     use bft::node::*;
-    use bft::message::Message;
+    use bft::message::{Message, MessageKind, Payload};
+    use bft::simulation::Simulation;
+    use bft::context::{Context, Timer};
+    use bft::streamlet::{StreamletNode, EPOCH_LENGTH};
 
     #[test]
     fn test_logging() {
@@ -87,7 +98,7 @@ mod tests {
             logging::log_info(&format!("Current epoch: {}", current.epoch()));
 
             // Determine the number of unique signatures required for notarization.
-            let required_signatures = proposal.t() + 1;
+            let required_signatures = proposal.t();
             // Add fewer signatures (all the same) so that notarization fails.
             for _ in 0..(required_signatures - 1) {
                 proposal.add_signature(0);
@@ -127,6 +138,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_streamlet_notarization_threshold() {
+        // A proposal is notarized by exactly t = ceil(2n / 3) distinct signatures.
+        for (n, t) in [(3, 2), (4, 3), (5, 4), (7, 5)] {
+            let genesis = StreamletGenesis::new(n);
+            assert_eq!(genesis.t, t);
+            let mut proposal = StreamletProposal::new(Box::new(genesis.last_final()), 1);
+            for id in 0..t - 1 {
+                proposal.add_signature(id);
+            }
+            assert!(!proposal.is_notarized());
+            proposal.add_signature(0);
+            assert!(!proposal.is_notarized());
+            proposal.add_signature(t - 1);
+            assert!(proposal.is_notarized());
+        }
+    }
+
     /// Test that asserting notarization on an under-signed proposal panics.
     #[test]
     #[should_panic(expected = "Proposal is not notarized")]
@@ -172,15 +201,12 @@ mod tests {
         logging::log_info(&format!("Created nodes: {}, {}", node1_id, node2_id));
 
         // Create a message from node1 to node2.
-        let message = Message {
-            content: "Hello, node2!".to_string(),
-            timestamp: 0,
-        };
+        let message = Message::text(0, "Hello, node2!");
 
         // Send the message with a delay of 100ms.
         {
             let mut net = network.lock().unwrap();
-            net.send(node1_id, node2_id, message.clone(), 100);
+            net.send_with_delay(node1_id, node2_id, message.clone(), 100);
             // Process the events to deliver the scheduled message.
             net.process_events();
         }
@@ -196,9 +222,12 @@ mod tests {
         if let Some(received_message) = received_message {
             logging::log_info(&format!(
                 "Node {} received message: {}",
-                node2_id, received_message.content
+                node2_id, received_message.payload
             ));
-            assert_eq!(received_message.content, "Hello, node2!");
+            assert_eq!(received_message.payload, Payload::Text("Hello, node2!".to_string()));
+            // The network stamps the sender on every message it carries.
+            assert_eq!(received_message.sender, node1_id);
+            assert_eq!(received_message.kind, MessageKind::Text);
         } else {
             logging::log_error("No message received.");
             panic!("No message received.");
@@ -210,15 +239,12 @@ mod tests {
         let mut node = PassiveNode::new(0);
 
         // Create a message for the node.
-        let message = Message {
-            content: "Hello, PassiveNode!".to_string(),
-            timestamp: 1,
-        };
+        let message = Message::text(0, "Hello, PassiveNode!").with_timestamp(1);
 
         // Use block_on to run the asynchronous code.
         block_on(async {
             // Await the future returned by handle.
-            node.handle(1, message).await;
+            node.handle(&mut Context::new(0, 1, 2), 1, message).await;
 
             // Run the node's main loop by awaiting all asynchronous effects.
             for effect in node.run() {
@@ -233,20 +259,15 @@ mod tests {
         let mut node = SequentialNode::new(0);
 
         // Create two messages.
-        let message1 = Message {
-            content: "Message 1".to_string(),
-            timestamp: 0,
-        };
-        let message2 = Message {
-            content: "Message 2".to_string(),
-            timestamp: 1,
-        };
+        let message1 = Message::text(0, "Message 1");
+        let message2 = Message::text(0, "Message 2").with_timestamp(1);
 
         // Use block_on to run the asynchronous code.
         block_on(async {
             // Await the futures returned by handle.
-            node.handle(1, message1).await;
-            node.handle(2, message2).await;
+            let mut ctx = Context::new(0, 1, 3);
+            node.handle(&mut ctx, 1, message1).await;
+            node.handle(&mut ctx, 2, message2).await;
 
             // Run the node's main loop by awaiting each effect.
             for effect in node.run() {
@@ -265,30 +286,24 @@ mod tests {
             timestamp: 10,
             sender: 0,
             receiver: 1,
-            message: Message {
-                content: "Event 1".to_string(),
-                timestamp: 10,
-            },
+            kind: EventKind::Deliver(Message::text(0, "Event 1").with_timestamp(10)),
+            seq: 0,
         };
 
         let event2 = Event {
             timestamp: 5,
             sender: 1,
             receiver: 0,
-            message: Message {
-                content: "Event 2".to_string(),
-                timestamp: 5,
-            },
+            kind: EventKind::Deliver(Message::text(0, "Event 2").with_timestamp(5)),
+            seq: 0,
         };
 
         let event3 = Event {
             timestamp: 7,
             sender: 2,
             receiver: 3,
-            message: Message {
-                content: "Event 3".to_string(),
-                timestamp: 7,
-            },
+            kind: EventKind::Deliver(Message::text(0, "Event 3").with_timestamp(7)),
+            seq: 0,
         };
 
         // Schedule events in the queue.
@@ -313,8 +328,594 @@ mod tests {
         for event in processed_events {
             util::logging::log_info(&format!(
                 "Timestamp: {}, Content: {}",
-                event.timestamp, event.message.content
+                event.timestamp, event.message().unwrap().payload
             ));
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_typed_messages() {
+        let proposal = Message::new(2, 7, Payload::Proposal { parent_epoch: 5 });
+        assert_eq!(proposal.kind, MessageKind::Proposal);
+        assert_eq!(proposal.epoch, 7);
+        assert_eq!(proposal.sender, 2);
+
+        let signed = Context::new(3, 0, 4).sign_vote(7, 5);
+        assert_eq!((signed.voter(), signed.epoch(), signed.parent_epoch()), (3, 7, 5));
+        let vote = Message::new(1, 7, Payload::Vote(signed));
+        assert_eq!(vote.kind, MessageKind::Vote);
+        assert_ne!(proposal.payload, vote.payload);
+    }
+
+    /// A node recording the time and text of every message and timer it handles, relaying
+    /// messages from node 0 to node 2, and setting a timer when asked to.
+    struct RelayNode {
+        log: Arc<Mutex<Vec<(u64, String)>>>,
+    }
+
+    impl Node for RelayNode {
+        fn handle(&mut self, ctx: &mut Context, sender: usize, message: Message) -> BoxFuture<'static, ()> {
+            if let Payload::Text(content) = &message.payload {
+                self.log.lock().unwrap().push((ctx.now(), content.clone()));
+                if sender == 0 && ctx.id() == 1 {
+                    ctx.send(2, Message::text(ctx.id(), format!("relayed {}", content)));
+                }
+                if content == "timer" {
+                    ctx.set_timer(10, 7);
+                }
+                if content == "cancel" {
+                    let id = ctx.set_timer(5, 8);
+                    ctx.set_timer(6, 9);
+                    ctx.cancel_timer(id);
+                }
+            }
+            async {}.boxed()
+        }
+
+        fn on_timer(&mut self, ctx: &mut Context, tag: u64) -> BoxFuture<'static, ()> {
+            self.log.lock().unwrap().push((ctx.now(), format!("timer {}", tag)));
+            ctx.broadcast(Message::text(ctx.id(), "woken"));
+            async {}.boxed()
+        }
+
+        fn run(&mut self) -> Box<dyn Iterator<Item = BoxFuture<'static, ()>> + Send + '_> {
+            Box::new(std::iter::empty())
+        }
+
+        fn propose(&mut self, _: String) -> BoxFuture<'static, ()> { async {}.boxed() }
+        fn vote(&mut self, _: usize, _: String) -> BoxFuture<'static, ()> { async {}.boxed() }
+        fn finalize(&mut self, _: String) -> BoxFuture<'static, Option<String>> { async { None }.boxed() }
+    }
+
+    #[test]
+    fn test_simulation_dispatch() {
+        let mut simulation = Simulation::new();
+        let log = Arc::new(Mutex::new(Vec::new()));
+        for _ in 0..3 {
+            simulation.add_node(RelayNode { log: log.clone() });
+        }
+        log.lock().unwrap().clear();
+
+        {
+            let mut network = simulation.network.lock().unwrap();
+            network.send_with_delay(0, 2, Message::text(0, "late"), 5);
+            network.send_with_delay(0, 1, Message::text(0, "early"), 1);
+            network.send_with_delay(0, 2, Message::text(0, "middle"), 3);
+        }
+        block_on(simulation.start());
+
+        // Messages are handled in timestamp order, and the relay sent while handling the
+        // message at time 1 is delivered at time 2, before the later scheduled messages.
+        let log = log.lock().unwrap();
+        let expected = [(1, "early"), (2, "relayed early"), (3, "middle"), (5, "late")];
+        assert_eq!(log.len(), expected.len());
+        for ((timestamp, content), (expected_timestamp, expected_content)) in log.iter().zip(expected) {
+            assert_eq!(*timestamp, expected_timestamp);
+            assert_eq!(content, expected_content);
+        }
+    }
+
+    #[test]
+    fn test_context_timers() {
+        let mut simulation = Simulation::new();
+        let log = Arc::new(Mutex::new(Vec::new()));
+        for _ in 0..2 {
+            simulation.add_node(RelayNode { log: log.clone() });
+        }
+        log.lock().unwrap().clear();
+
+        simulation.network.lock().unwrap().send_with_delay(1, 1, Message::text(1, "timer"), 3);
+        block_on(simulation.start());
+
+        // The timer set at time 3 fires at time 13, and the broadcast it triggers reaches
+        // both nodes after the network delay of 1.
+        let log = log.lock().unwrap();
+        let expected = [(3, "timer"), (13, "timer 7"), (14, "woken"), (14, "woken")];
+        assert_eq!(log.len(), expected.len());
+        for ((time, content), (expected_time, expected_content)) in log.iter().zip(expected) {
+            assert_eq!(*time, expected_time);
+            assert_eq!(content, expected_content);
+        }
+    }
+
+    #[test]
+    fn test_cancel_timer() {
+        let mut simulation = Simulation::new();
+        let log = Arc::new(Mutex::new(Vec::new()));
+        simulation.add_node(RelayNode { log: log.clone() });
+        log.lock().unwrap().clear();
+
+        simulation.network.lock().unwrap().send_with_delay(0, 0, Message::text(0, "cancel"), 1);
+        block_on(simulation.start());
+
+        // Only the timer that was not cancelled fires; its broadcast reaches the single node.
+        let log = log.lock().unwrap();
+        let expected = [(1, "cancel"), (7, "timer 9"), (8, "woken")];
+        assert_eq!(log.len(), expected.len());
+        for ((time, content), (expected_time, expected_content)) in log.iter().zip(expected) {
+            assert_eq!(*time, expected_time);
+            assert_eq!(content, expected_content);
+        }
+    }
+
+    #[test]
+    fn test_context_outbox() {
+        let mut ctx = Context::new(1, 5, 3);
+        ctx.broadcast(Message::text(0, "hello"));
+        ctx.send(2, Message::text(0, "direct"));
+        let id = ctx.set_timer(4, 9);
+
+        // Messages are stamped with the context's node and time.
+        let targets: Vec<usize> = ctx.sent().iter().map(|(to, _)| *to).collect();
+        assert_eq!(targets, vec![0, 1, 2, 2]);
+        assert!(ctx.sent().iter().all(|(_, message)| message.sender == 1 && message.timestamp == 5));
+        assert_eq!(ctx.timers(), &[Timer { id, delay: 4, tag: 9 }]);
+    }
+
+    #[test]
+    fn test_event_queue_tie_breaking() {
+        let mut event_queue = EventQueue::new();
+        let event = |sender, receiver, content: &str| Event {
+            timestamp: 3,
+            sender,
+            receiver,
+            kind: EventKind::Deliver(Message::text(sender, content)),
+            seq: 0,
+        };
+        event_queue.schedule(event(1, 0, "a"));
+        event_queue.schedule(event(0, 2, "b"));
+        event_queue.schedule(event(0, 1, "c"));
+        event_queue.schedule(event(0, 1, "d"));
+
+        // Same-time events pop by sender, then receiver, then insertion order.
+        let mut order = Vec::new();
+        while let Some(event) = event_queue.process_next_event() {
+            order.push(event.message().unwrap().payload.to_string());
+        }
+        assert_eq!(order, vec!["c", "d", "b", "a"]);
+    }
+
+    /// A node that broadcasts a random number on wake-up and records every number it receives.
+    struct RandomNode {
+        log: Arc<Mutex<Vec<(u64, usize, String)>>>,
+    }
+
+    impl Node for RandomNode {
+        fn handle(&mut self, ctx: &mut Context, sender: usize, message: Message) -> BoxFuture<'static, ()> {
+            self.log.lock().unwrap().push((ctx.now(), sender, message.payload.to_string()));
+            async {}.boxed()
+        }
+
+        fn on_wake_up(&mut self, ctx: &mut Context) -> BoxFuture<'static, ()> {
+            let value: u64 = ctx.rng().gen();
+            ctx.broadcast(Message::text(ctx.id(), value.to_string()));
+            async {}.boxed()
+        }
+
+        fn run(&mut self) -> Box<dyn Iterator<Item = BoxFuture<'static, ()>> + Send + '_> {
+            Box::new(std::iter::empty())
+        }
+
+        fn propose(&mut self, _: String) -> BoxFuture<'static, ()> { async {}.boxed() }
+        fn vote(&mut self, _: usize, _: String) -> BoxFuture<'static, ()> { async {}.boxed() }
+        fn finalize(&mut self, _: String) -> BoxFuture<'static, Option<String>> { async { None }.boxed() }
+    }
+
+    fn run_random(seed: u64) -> Vec<(u64, usize, String)> {
+        let mut simulation = Simulation::with_seed(seed);
+        let log = Arc::new(Mutex::new(Vec::new()));
+        for id in 0..3 {
+            simulation.add_node(RandomNode { log: log.clone() });
+            simulation.wake_up(id, 0);
+        }
+        log.lock().unwrap().clear();
+        block_on(simulation.start());
+        let log = log.lock().unwrap().clone();
+        log
+    }
+
+    #[test]
+    fn test_seeded_simulation_is_reproducible() {
+        let run = run_random(7);
+        assert_eq!(run.len(), 9);
+        assert_eq!(run, run_random(7));
+        assert_ne!(run, run_random(8));
+    }
+
+    /// Adds `n` Streamlet nodes to a simulation and wakes them up at time 0. Nodes listed in
+    /// `silent` never send anything.
+    fn add_streamlet_nodes(simulation: &mut Simulation, n: usize, silent: &[usize]) {
+        for id in 0..n {
+            if silent.contains(&id) {
+                simulation.add_node(ByzantineNode::new(id));
+            } else {
+                simulation.add_node(StreamletNode::new(id, n));
+            }
+        }
+        for id in 0..n {
+            simulation.wake_up(id, 0);
+        }
+    }
+
+    /// Returns the epochs up to `epochs` that each honest node has finalized.
+    fn finalized_epochs(simulation: &Simulation, n: usize, silent: &[usize], epochs: usize) -> Vec<Vec<usize>> {
+        (0..n)
+            .filter(|id| !silent.contains(id))
+            .map(|id| {
+                (0..=epochs)
+                    .filter(|epoch| {
+                        let future = simulation.nodes[id].lock().unwrap().finalize(epoch.to_string());
+                        block_on(future).is_some()
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Runs Streamlet with `n` nodes for a number of epochs and returns the finalized epochs
+    /// of each honest node. Nodes listed in `silent` never send anything.
+    fn run_streamlet_on(simulation: &mut Simulation, n: usize, silent: &[usize], epochs: usize) -> Vec<Vec<usize>> {
+        add_streamlet_nodes(simulation, n, silent);
+        // Epoch e runs from (e - 1) * EPOCH_LENGTH until the next epoch starts.
+        block_on(simulation.run_until(epochs as u64 * EPOCH_LENGTH - 1));
+        finalized_epochs(simulation, n, silent, epochs)
+    }
+
+    fn run_streamlet(n: usize, silent: &[usize], epochs: usize) -> Vec<Vec<usize>> {
+        run_streamlet_on(&mut Simulation::new(), n, silent, epochs)
+    }
+
+    #[test]
+    fn test_streamlet_simulation() {
+        // Every epoch is notarized, so epochs 4, 5 and 6 finalize epoch 5 and its prefix.
+        let mut simulation = Simulation::new();
+        let finalized = run_streamlet_on(&mut simulation, 4, &[], 6);
+        for node_final in finalized {
+            assert_eq!(node_final, vec![0, 1, 2, 3, 4, 5]);
+        }
+        // The last relayed votes arrive three message delays into epoch 6.
+        assert_eq!(simulation.now(), 5 * EPOCH_LENGTH + 3);
+    }
+
+    #[test]
+    fn test_streamlet_simulation_silent_leader() {
+        // Node 1 never votes or proposes, so epochs 1 and 5 are empty. The three honest
+        // nodes still reach t = 3 votes, and epochs 6, 7 and 8 finalize epoch 7.
+        let finalized = run_streamlet(4, &[1], 8);
+        for node_final in finalized {
+            assert_eq!(node_final, vec![0, 2, 3, 4, 6, 7]);
+        }
+    }
+
+    /// A Byzantine node voting for every proposal on every parent, and replaying every vote it
+    /// receives under later epochs, trying to notarize blocks without honest signatures.
+    struct ForgingNode;
+
+    impl Node for ForgingNode {
+        fn handle(&mut self, ctx: &mut Context, sender: usize, message: Message) -> BoxFuture<'static, ()> {
+            match message.payload {
+                Payload::Proposal { .. } => {
+                    for parent in 0..message.epoch {
+                        let vote = ctx.sign_vote(message.epoch, parent);
+                        ctx.broadcast(Message::new(ctx.id(), message.epoch, Payload::Vote(vote)));
+                    }
+                }
+                Payload::Vote(vote) if sender != ctx.id() => {
+                    for epoch in vote.epoch() + 1..=vote.epoch() + 3 {
+                        ctx.broadcast(Message::new(ctx.id(), epoch, Payload::Vote(vote)));
+                    }
+                }
+                _ => {}
+            }
+            async {}.boxed()
+        }
+
+        fn run(&mut self) -> Box<dyn Iterator<Item = BoxFuture<'static, ()>> + Send + '_> {
+            Box::new(std::iter::empty())
+        }
+
+        fn propose(&mut self, _: String) -> BoxFuture<'static, ()> { async {}.boxed() }
+        fn vote(&mut self, _: usize, _: String) -> BoxFuture<'static, ()> { async {}.boxed() }
+        fn finalize(&mut self, _: String) -> BoxFuture<'static, Option<String>> { async { None }.boxed() }
+    }
+
+    #[test]
+    fn test_streamlet_forged_votes() {
+        // Votes are signed, so the forging node 3 only ever adds its own vote, however it
+        // relays the others. With nodes 1 and 2 silent, node 0 never sees t = 3 signers and
+        // finalizes nothing but the genesis block.
+        let mut simulation = Simulation::new();
+        simulation.add_node(StreamletNode::new(0, 4));
+        simulation.add_node(ByzantineNode::new(1));
+        simulation.add_node(ByzantineNode::new(2));
+        simulation.add_node(ForgingNode);
+        for id in 0..4 {
+            simulation.wake_up(id, 0);
+        }
+        block_on(simulation.run_until(12 * EPOCH_LENGTH));
+
+        let finalized: Vec<usize> = (0..=12)
+            .filter(|epoch| {
+                let future = simulation.nodes[0].lock().unwrap().finalize(epoch.to_string());
+                block_on(future).is_some()
+            })
+            .collect();
+        assert_eq!(finalized, vec![0]);
+    }
+
+    fn sample_delays(model: &dyn DelayModel, from: usize, to: usize, samples: usize) -> Vec<u64> {
+        let mut rng = StdRng::seed_from_u64(1);
+        (0..samples).map(|_| model.delay(from, to, &mut rng)).collect()
+    }
+
+    #[test]
+    fn test_delay_models() {
+        assert_eq!(sample_delays(&ConstantDelay(4), 0, 1, 10), vec![4; 10]);
+
+        let uniform = sample_delays(&UniformDelay::new(2, 5), 0, 1, 1_000);
+        assert!(uniform.iter().all(|delay| (2..=5).contains(delay)));
+        assert!((2..=5).all(|delay| uniform.contains(&delay)));
+
+        let exponential = sample_delays(&ExponentialDelay { mean: 20.0 }, 0, 1, 10_000);
+        let mean = exponential.iter().sum::<u64>() as f64 / exponential.len() as f64;
+        assert!((mean - 20.0).abs() < 1.0, "mean exponential delay {}", mean);
+
+        let mut log_normal = sample_delays(&LogNormalDelay::with_median(50.0, 0.5), 0, 1, 10_000);
+        log_normal.sort();
+        let median = log_normal[log_normal.len() / 2] as f64;
+        assert!((median - 50.0).abs() < 2.0, "median log-normal delay {}", median);
+
+        let matrix = MatrixDelay::new(vec![vec![0, 3], vec![7, 0]], 9);
+        assert_eq!(sample_delays(&matrix, 0, 1, 1), vec![3]);
+        assert_eq!(sample_delays(&matrix, 1, 0, 1), vec![7]);
+        assert_eq!(sample_delays(&matrix, 2, 0, 1), vec![9]);
+    }
+
+    #[test]
+    fn test_streamlet_simulation_uniform_delay() {
+        // Proposals and votes take up to 4 time units each, so both fit in an epoch of 10.
+        let mut simulation = Simulation::with_seed(3);
+        simulation.network.lock().unwrap().set_delay_model(UniformDelay::new(1, 4));
+        let finalized = run_streamlet_on(&mut simulation, 4, &[], 6);
+        for node_final in finalized {
+            assert_eq!(node_final, vec![0, 1, 2, 3, 4, 5]);
+        }
+    }
+
+    #[test]
+    fn test_network_partition() {
+        let mut network = Network::new();
+        for _ in 0..3 {
+            network.add_node();
+        }
+        network.add_partition(Partition::new(vec![vec![0]], 10, 20, HeldMessages::Drop));
+        network.add_partition(Partition::new(vec![vec![1]], 30, 40, HeldMessages::DeliverOnHeal));
+
+        // Across the first split: dropped. Within the unlisted group {1, 2}: delivered.
+        network.send_with_delay(0, 1, Message::text(0, "dropped").with_timestamp(12), 2);
+        network.send_with_delay(1, 2, Message::text(1, "same side").with_timestamp(12), 2);
+        // Across the second split: held until time 40, then delayed as usual.
+        network.send_with_delay(2, 1, Message::text(2, "held").with_timestamp(35), 2);
+        // After healing: delivered normally.
+        network.send_with_delay(0, 1, Message::text(0, "healed").with_timestamp(40), 2);
+
+        let mut delivered = Vec::new();
+        while let Some(event) = network.event_queue.process_next_event() {
+            delivered.push((event.timestamp, event.message().unwrap().payload.to_string()));
+        }
+        let expected = [(14, "same side"), (42, "healed"), (42, "held")];
+        assert_eq!(delivered.len(), expected.len());
+        for ((time, content), (expected_time, expected_content)) in delivered.iter().zip(expected) {
+            assert_eq!(*time, expected_time);
+            assert_eq!(content, expected_content);
+        }
+        assert_eq!(
+            network.stats(),
+            NetworkStats { sent: 4, partition_dropped: 1, partition_held: 1, ..NetworkStats::default() }
+        );
+    }
+
+    #[test]
+    fn test_streamlet_partition_heals() {
+        // Splitting 4 nodes in halves during epochs 2 to 4 leaves no side with t = 3 votes, so
+        // nothing is notarized, let alone finalized on either side. After healing, epochs 5 to 9
+        // extend epoch 1 and epochs 7, 8 and 9 finalize epoch 8.
+        for held in [HeldMessages::Drop, HeldMessages::DeliverOnHeal] {
+            let mut simulation = Simulation::new();
+            let partition = Partition::new(vec![vec![0, 1], vec![2, 3]], EPOCH_LENGTH, 4 * EPOCH_LENGTH, held);
+            simulation.network.lock().unwrap().add_partition(partition);
+            let finalized = run_streamlet_on(&mut simulation, 4, &[], 9);
+            for node_final in finalized {
+                assert_eq!(node_final, vec![0, 1, 5, 6, 7, 8]);
+            }
+
+            let stats = simulation.network.lock().unwrap().stats();
+            match held {
+                HeldMessages::Drop => assert!(stats.partition_dropped > 0 && stats.partition_held == 0),
+                HeldMessages::DeliverOnHeal => assert!(stats.partition_held > 0 && stats.partition_dropped == 0),
+            }
+        }
+    }
+
+    #[test]
+    fn test_link_faults() {
+        let mut network = Network::new();
+        for _ in 0..3 {
+            network.add_node();
+        }
+        network.set_faults(LinkFaults { drop: 0.2, duplicate: 0.3, reorder: 0.5, max_jitter: 5 });
+        network.set_link_faults(0, 2, LinkFaults::lossy(1.0));
+
+        let mut rng = StdRng::seed_from_u64(1);
+        for i in 0..1_000 {
+            network.send(0, 1, Message::text(0, i.to_string()), &mut rng);
+        }
+        for i in 0..100 {
+            network.send(0, 2, Message::text(0, i.to_string()), &mut rng);
+        }
+
+        let stats = network.stats();
+        // Every message on the lossy link is lost, and about a fifth of the others.
+        assert!((100 + 150..=100 + 250).contains(&stats.lost), "lost {}", stats.lost);
+        assert!((200..=280).contains(&stats.duplicated), "duplicated {}", stats.duplicated);
+        assert_eq!(stats.sent, 1_000 + stats.duplicated - (stats.lost - 100));
+
+        // Jittered copies arrive later than the constant delay of 1, out of send order.
+        let mut delivered = Vec::new();
+        while let Some(event) = network.event_queue.process_next_event() {
+            assert!((1..=6).contains(&event.timestamp));
+            assert_eq!(event.receiver, 1);
+            delivered.push(event.timestamp);
+        }
+        assert_eq!(delivered.len() as u64, stats.sent);
+        assert_eq!(delivered.iter().filter(|time| **time > 1).count() as u64, stats.reordered);
+    }
+
+    #[test]
+    fn test_streamlet_lossy_links() {
+        // Duplicates are idempotent for Streamlet, and jitter of up to 3 still fits a proposal
+        // and its votes in an epoch, so every epoch is notarized.
+        let mut simulation = Simulation::with_seed(5);
+        let faults = LinkFaults { drop: 0.0, duplicate: 0.5, reorder: 0.5, max_jitter: 3 };
+        simulation.network.lock().unwrap().set_faults(faults);
+        for node_final in run_streamlet_on(&mut simulation, 4, &[], 6) {
+            assert_eq!(node_final, vec![0, 1, 2, 3, 4, 5]);
+        }
+        let stats = simulation.network.lock().unwrap().stats();
+        assert!(stats.duplicated > 0 && stats.reordered > 0);
+
+        // Lost proposals and votes leave some epochs unnotarized, but votes are relayed, so
+        // the honest nodes never finalize conflicting chains and each keeps finalizing later
+        // epochs. Runs with the same seed agree on their common prefix, so longer runs show
+        // every node's last final epoch advancing.
+        let mut last_finals = [0; 4];
+        for epochs in [12, 24, 48] {
+            let mut simulation = Simulation::with_seed(5);
+            simulation.network.lock().unwrap().set_faults(LinkFaults::lossy(0.05));
+            let finalized = run_streamlet_on(&mut simulation, 4, &[], epochs);
+            for a in &finalized {
+                for b in &finalized {
+                    let common = a.len().min(b.len());
+                    assert_eq!(a[..common], b[..common]);
+                }
+            }
+            for (last_final, node_final) in last_finals.iter_mut().zip(&finalized) {
+                let latest = *node_final.last().unwrap();
+                assert!(latest > *last_final, "stuck at epoch {} after {} epochs", latest, epochs);
+                *last_final = latest;
+            }
+            assert!(simulation.network.lock().unwrap().stats().lost > 0);
+        }
+    }
+
+    #[test]
+    fn test_partial_synchrony() {
+        let mut network = Network::new();
+        for _ in 0..2 {
+            network.add_node();
+        }
+        network.set_delay_model(UniformDelay::new(1, 10));
+        network.set_partial_synchrony(PartialSynchrony::new(50, 4, UniformDelay::new(0, 1_000)));
+        // The adversary alone schedules messages sent before GST, so only later ones get jitter.
+        network.set_faults(LinkFaults { reorder: 1.0, max_jitter: 2, ..LinkFaults::default() });
+
+        let mut rng = StdRng::seed_from_u64(2);
+        for time in 0..100 {
+            network.send(0, 1, Message::text(0, time.to_string()).with_timestamp(time), &mut rng);
+        }
+        assert_eq!(network.stats().adversary_scheduled, 50);
+        assert_eq!(network.stats().reordered, 50);
+
+        let mut late_before_gst = 0;
+        while let Some(event) = network.event_queue.process_next_event() {
+            let sent_at: u64 = event.message().unwrap().payload.to_string().parse().unwrap();
+            // Every message arrives within Δ of GST or of being sent, whichever is later.
+            assert!(event.timestamp <= sent_at.max(50) + 4);
+            if sent_at < 50 && event.timestamp > sent_at + 4 {
+                late_before_gst += 1;
+            }
+        }
+        assert!(late_before_gst > 0);
+    }
+
+    #[test]
+    fn test_partial_synchrony_bounds_partitions() {
+        // A partition holding messages until after the deadline, or a duplicated message, still
+        // arrives within Δ of GST or of being sent.
+        let mut network = Network::new();
+        for _ in 0..2 {
+            network.add_node();
+        }
+        network.add_partition(Partition::new(vec![vec![0]], 0, 200, HeldMessages::DeliverOnHeal));
+        network.set_partial_synchrony(PartialSynchrony::new(50, 4, WorstCaseAdversary));
+        network.set_faults(LinkFaults { duplicate: 1.0, ..LinkFaults::default() });
+
+        let mut rng = StdRng::seed_from_u64(3);
+        for time in [10, 60, 120] {
+            network.send(0, 1, Message::text(0, time.to_string()).with_timestamp(time), &mut rng);
+        }
+        let stats = network.stats();
+        assert_eq!((stats.partition_held, stats.duplicated), (6, 3));
+
+        let mut delivered = Vec::new();
+        while let Some(event) = network.event_queue.process_next_event() {
+            delivered.push(event.timestamp);
+        }
+        assert_eq!(delivered, vec![54, 54, 64, 64, 124, 124]);
+
+        // Without partial synchrony the partition holds them until it heals.
+        let mut network = Network::new();
+        for _ in 0..2 {
+            network.add_node();
+        }
+        network.add_partition(Partition::new(vec![vec![0]], 0, 200, HeldMessages::DeliverOnHeal));
+        network.send(0, 1, Message::text(0, "10").with_timestamp(10), &mut rng);
+        let event = network.event_queue.process_next_event().unwrap();
+        assert!(event.timestamp >= 200);
+    }
+
+    #[test]
+    fn test_streamlet_liveness_after_gst() {
+        // Before GST, at the start of epoch 6, the adversary delays proposals and votes past
+        // their epochs, so nothing is finalized. From GST on, Δ = 4 fits a proposal and its
+        // votes in an epoch, so epochs 6 to 10 are notarized and epochs 8, 9 and 10 finalize
+        // epoch 9 on top of the genesis block.
+        let gst = 5 * EPOCH_LENGTH;
+        for seed in 0..3 {
+            let adversaries = [
+                PartialSynchrony::new(gst, 4, WorstCaseAdversary),
+                PartialSynchrony::new(gst, 4, UniformDelay::new(0, 3 * EPOCH_LENGTH)),
+            ];
+            for synchrony in adversaries {
+                let mut simulation = Simulation::with_seed(seed);
+                simulation.network.lock().unwrap().set_partial_synchrony(synchrony);
+                add_streamlet_nodes(&mut simulation, 4, &[]);
+                block_on(simulation.assert_live_after_gst(&[0, 1, 2, 3], 5 * EPOCH_LENGTH - 1, 10));
+                for node_final in finalized_epochs(&simulation, 4, &[], 10) {
+                    assert_eq!(node_final, vec![0, 6, 7, 8, 9]);
+                }
+                assert!(simulation.network.lock().unwrap().stats().adversary_scheduled > 0);
+            }
+        }
+    }
+}
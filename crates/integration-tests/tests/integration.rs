@@ -1,11 +1,12 @@
 // tests/integration.rs
 #[cfg(test)]
 mod tests {
+    use bc::amount::Amount;
     use bc::context::BCContext;
-    use bc::transaction::{BCTransaction, TXO, Note};
+    use bc::transaction::{Address, BCTransaction, TXO, Note};
     use bft::simulation::Simulation;
     use bft::node::{HonestNode, ByzantineNode};
-    use bft::message::Message;
+    use bft::message::{Message, Payload};
     use async_std::task;
     use util::logging;
 
@@ -26,18 +27,21 @@ mod tests {
                     transparent_outputs: Vec::new(),
                     shielded_inputs: Vec::new(),
                     shielded_outputs: Vec::new(),
-                    fee: 0,
+                    fee: Amount::ZERO,
                     anchor: None,
-                    issuance: 10,
+                    issuance: Amount::const_from_i64(10),
+                    expiry_height: 0,
                 },
                 index: 0,
-                value: 10,
+                value: Amount::const_from_i64(10),
+                address: Address(0),
             }],
             shielded_inputs: Vec::new(),
-            shielded_outputs: vec![Note { value: 5 }],
-            fee: 0,
+            shielded_outputs: vec![Note { value: Amount::const_from_i64(5) }],
+            fee: Amount::ZERO,
             anchor: None,
-            issuance: 10,
+            issuance: Amount::const_from_i64(10),
+            expiry_height: 0,
         };
 
         // Step 3: Add the transaction to the context
@@ -78,15 +82,12 @@ mod tests {
             net.add_node()
         };
 
-        let message = Message {
-            content: "Propose Block".to_string(),
-            timestamp: 0,
-        };
+        let message = Message::text(0, "Propose Block");
 
         {
             // Lock the network to send the message.
             let mut net = network.lock().unwrap();
-            net.send(node1_id, node2_id, message.clone(), 100);
+            net.send_with_delay(node1_id, node2_id, message.clone(), 100);
             // Process events to deliver the scheduled message.
             net.process_events();
         }
@@ -100,9 +101,9 @@ mod tests {
         if let Some(received_message) = received_message {
             logging::log_info(&format!(
                 "Node {} received message: {}",
-                node2_id, received_message.content
+                node2_id, received_message.payload
             ));
-            assert_eq!(received_message.content, "Propose Block");
+            assert_eq!(received_message.payload, Payload::Text("Propose Block".to_string()));
         } else {
             logging::log_error("No message received.");
             panic!("No message received.");